@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::{CameraFollow, CameraZoom, controlled_camera, viewport::PrimaryCamera},
+    input::{InputMapping, InputState},
+    modding::registry::{Id, Registry},
+};
+
+/// How many bookmarks there are, numbered from one
+pub const BOOKMARK_COUNT: u8 = 9;
+
+/// A saved camera view
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CameraBookmark {
+    pub slot: u8,
+    pub position: Vec2,
+    pub scale: f32,
+}
+
+/// The player's numbered camera views, kept with the world they were made in
+#[derive(Debug, Default, Clone, PartialEq, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraBookmarks {
+    bookmarks: Vec<CameraBookmark>,
+}
+
+impl CameraBookmarks {
+    pub fn get(&self, slot: u8) -> Option<&CameraBookmark> {
+        self.bookmarks.iter().find(|b| b.slot == slot)
+    }
+
+    /// Saves a view to `slot`, replacing any already there
+    pub fn set(&mut self, slot: u8, position: Vec2, scale: f32) {
+        let bookmark = CameraBookmark {
+            slot,
+            position,
+            scale,
+        };
+        match self.bookmarks.iter_mut().find(|b| b.slot == slot) {
+            Some(existing) => *existing = bookmark,
+            None => {
+                self.bookmarks.push(bookmark);
+                self.bookmarks.sort_by_key(|b| b.slot);
+            }
+        }
+    }
+}
+
+/// The path of the action recalling bookmark `slot`
+pub fn recall_path(slot: u8) -> String {
+    format!("base::input::bookmark_{slot}")
+}
+
+/// The path of the action saving the view to bookmark `slot`
+pub fn save_path(slot: u8) -> String {
+    format!("base::input::save_bookmark_{slot}")
+}
+
+#[derive(Debug, Resource)]
+pub(super) struct BookmarkInputIds {
+    /// The recall and save actions for each slot
+    slots: Vec<(u8, Id, Id)>,
+}
+
+pub(super) fn init_bookmark_input_ids(mut commands: Commands, inputs: Res<Registry<InputMapping>>) {
+    let ids = BookmarkInputIds {
+        slots: (1..=BOOKMARK_COUNT)
+            .map(|slot| {
+                (
+                    slot,
+                    inputs.lookup(&recall_path(slot)).unwrap(),
+                    inputs.lookup(&save_path(slot)).unwrap(),
+                )
+            })
+            .collect(),
+    };
+    commands.insert_resource(ids);
+}
+
+/// Saves the controlled camera's view to a bookmark, or eases it back to one
+pub(super) fn use_bookmarks(
+    mut query: Query<(&Transform, &mut CameraZoom, &mut CameraFollow), With<Camera>>,
+    primary: Query<Entity, With<PrimaryCamera>>,
+    mut bookmarks: ResMut<CameraBookmarks>,
+    input: Res<InputState>,
+    ids: Res<BookmarkInputIds>,
+) {
+    for &(slot, recall, save) in ids.slots.iter() {
+        if !input.just_pressed(recall) && !input.just_pressed(save) {
+            continue;
+        }
+        let Some(camera) = controlled_camera(&input, |entity| query.contains(entity), &primary)
+        else {
+            return;
+        };
+        let Ok((transform, mut zoom, mut follow)) = query.get_mut(camera) else {
+            return;
+        };
+
+        if input.just_pressed(save) {
+            // Save where the camera is heading, so a bookmark made mid transition isn't
+            // somewhere in between
+            let position = follow
+                .point
+                .unwrap_or_else(|| transform.translation.truncate());
+            bookmarks.set(slot, position, zoom.target);
+            info!("saved camera bookmark {}", slot);
+        } else if let Some(bookmark) = bookmarks.get(slot) {
+            follow.move_to(bookmark.position);
+            zoom.set_target(bookmark.scale);
+        }
+    }
+}
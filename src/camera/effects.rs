@@ -0,0 +1,232 @@
+use bevy::{picking::Pickable, prelude::*};
+
+use crate::camera::settings::CameraSettings;
+
+/// Feedback played through the camera, such as for explosions and heavy impacts
+#[derive(Debug, Clone, Copy, PartialEq, Message)]
+pub enum CameraEffect {
+    /// Adds trauma, from zero to one. The camera shakes with the square of its trauma, which
+    /// wears off over time.
+    Trauma(f32),
+    /// Briefly zooms in by `strength`, as a fraction of the current scale
+    ZoomPunch(f32),
+    /// Fills the screen with `color`, fading out over `duration` seconds
+    Flash { color: Color, duration: f32 },
+}
+
+/// The effects playing on a camera, kept apart from its transform so they never move the camera
+/// the player controls
+#[derive(Debug, Default, Clone, Component)]
+pub struct CameraEffects {
+    pub trauma: f32,
+    pub punch: f32,
+    /// How long the effects have been playing, which drives the shake
+    time: f32,
+    /// The offset, angle and scale applied this frame, to be undone next frame
+    applied: Option<(Vec2, f32, f32)>,
+}
+
+/// A full screen overlay fading out after a flash
+#[derive(Debug, Component)]
+pub struct ScreenFlash {
+    color: Color,
+    duration: f32,
+    remaining: f32,
+}
+
+/// How many times a second the shake changes direction
+const SHAKE_FREQUENCY: f32 = 15.0;
+
+/// How quickly a zoom punch wears off, as a rate per second
+const PUNCH_DECAY: f32 = 8.0;
+
+pub(super) fn receive_camera_effects(
+    mut commands: Commands,
+    mut effects: MessageReader<CameraEffect>,
+    mut cameras: Query<&mut CameraEffects>,
+    mut flashes: Query<&mut ScreenFlash>,
+    settings: Res<CameraSettings>,
+) {
+    for effect in effects.read() {
+        match *effect {
+            CameraEffect::Trauma(amount) => {
+                for mut camera in cameras.iter_mut() {
+                    camera.trauma = (camera.trauma + amount).clamp(0.0, 1.0);
+                }
+            }
+            CameraEffect::ZoomPunch(strength) => {
+                for mut camera in cameras.iter_mut() {
+                    camera.punch = camera.punch.max(strength.clamp(0.0, 0.9));
+                }
+            }
+            CameraEffect::Flash { color, duration } => {
+                if settings.effects_intensity <= 0.0 || duration <= 0.0 {
+                    continue;
+                }
+
+                // A new flash replaces the one already showing
+                if let Some(mut flash) = flashes.iter_mut().next() {
+                    *flash = ScreenFlash {
+                        color,
+                        duration,
+                        remaining: duration,
+                    };
+                    continue;
+                }
+                commands.spawn((
+                    ScreenFlash {
+                        color,
+                        duration,
+                        remaining: duration,
+                    },
+                    Node {
+                        position_type: PositionType::Absolute,
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::NONE),
+                    GlobalZIndex(i32::MAX),
+                    Pickable::IGNORE,
+                ));
+            }
+        }
+    }
+}
+
+/// Undoes last frame's effects, so the rest of the frame sees the camera where the player put it
+pub(super) fn remove_camera_effects(
+    mut cameras: Query<(&mut Transform, &mut Projection, &mut CameraEffects)>,
+) {
+    for (mut transform, mut projection, mut effects) in cameras.iter_mut() {
+        let Some((offset, angle, scale)) = effects.applied.take() else {
+            continue;
+        };
+
+        transform.translation -= offset.extend(0.0);
+        transform.rotate_z(-angle);
+        if let Projection::Orthographic(ortho) = projection.as_mut() {
+            ortho.scale /= scale;
+        }
+    }
+}
+
+/// Applies the effects just before the camera's transform is propagated for rendering
+pub(super) fn apply_camera_effects(
+    mut cameras: Query<(&mut Transform, &mut Projection, &mut CameraEffects)>,
+    settings: Res<CameraSettings>,
+    time: Res<Time>,
+) {
+    let delta_secs = time.delta_secs();
+
+    for (mut transform, mut projection, mut effects) in cameras.iter_mut() {
+        effects.trauma = (effects.trauma - settings.trauma_decay * delta_secs).max(0.0);
+        effects.punch *= (-PUNCH_DECAY * delta_secs).exp();
+        if effects.trauma == 0.0 && effects.punch < 1e-3 {
+            effects.punch = 0.0;
+            effects.time = 0.0;
+            continue;
+        }
+        effects.time += delta_secs;
+
+        let Projection::Orthographic(ortho) = projection.as_mut() else {
+            continue;
+        };
+
+        let shake = effects.trauma * effects.trauma * settings.effects_intensity;
+        let t = effects.time * SHAKE_FREQUENCY;
+        let offset =
+            Vec2::new(noise(t, 0.0), noise(t, 1.0)) * settings.shake_offset * shake * ortho.scale;
+        let angle = noise(t, 2.0) * settings.shake_angle * shake;
+        let scale = 1.0 - effects.punch * settings.effects_intensity.min(1.0);
+
+        transform.translation += offset.extend(0.0);
+        transform.rotate_z(angle);
+        ortho.scale *= scale;
+        effects.applied = Some((offset, angle, scale));
+    }
+}
+
+pub(super) fn fade_screen_flashes(
+    mut commands: Commands,
+    mut flashes: Query<(Entity, &mut ScreenFlash, &mut BackgroundColor)>,
+    settings: Res<CameraSettings>,
+    time: Res<Time>,
+) {
+    for (entity, mut flash, mut background) in flashes.iter_mut() {
+        flash.remaining -= time.delta_secs();
+        if flash.remaining <= 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let fade = flash.remaining / flash.duration * settings.effects_intensity.min(1.0);
+        let alpha = flash.color.alpha() * fade;
+        background.set_if_neq(BackgroundColor(flash.color.with_alpha(alpha)));
+    }
+}
+
+/// Smooth noise from -1 to 1, with `seed` picking an unrelated curve
+fn noise(t: f32, seed: f32) -> f32 {
+    let a = (t + seed * 12.9898).sin();
+    let b = (t * 2.31 + seed * 78.233).sin();
+    let c = (t * 4.17 + seed * 37.719).sin();
+    (a * 0.5 + b * 0.3 + c * 0.2).clamp(-1.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::{TimePlugin, TimeUpdateStrategy};
+
+    use super::*;
+
+    /// Shakes and punches a camera for a frame, then checks the next frame starts from where the
+    /// camera was before
+    #[test]
+    fn applies_and_removes_effects() {
+        let mut app = App::new();
+        app.add_plugins(TimePlugin)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                1.0 / 60.0,
+            )))
+            .init_resource::<CameraSettings>()
+            .add_message::<CameraEffect>()
+            .add_systems(First, remove_camera_effects)
+            .add_systems(Update, receive_camera_effects)
+            .add_systems(PostUpdate, apply_camera_effects);
+        let camera = app
+            .world_mut()
+            .spawn((
+                Transform::default(),
+                Projection::Orthographic(OrthographicProjection::default_2d()),
+                CameraEffects::default(),
+            ))
+            .id();
+
+        // Time starts on the first update, so run one before the effects to give them a length
+        app.update();
+        app.world_mut()
+            .write_message(CameraEffect::Trauma(1.0))
+            .unwrap();
+        app.world_mut()
+            .write_message(CameraEffect::ZoomPunch(0.5))
+            .unwrap();
+        app.update();
+
+        let scale = |app: &App| match app.world().get::<Projection>(camera).unwrap() {
+            Projection::Orthographic(ortho) => ortho.scale,
+            _ => unreachable!(),
+        };
+        let transform = *app.world().get::<Transform>(camera).unwrap();
+        assert_ne!(transform.translation, Vec3::ZERO);
+        assert!(scale(&app) < 1.0);
+
+        app.world_mut().run_schedule(First);
+        let transform = *app.world().get::<Transform>(camera).unwrap();
+        assert!(transform.translation.length() < 1e-4);
+        assert!(transform.rotation.abs_diff_eq(Quat::IDENTITY, 1e-6));
+        assert!((scale(&app) - 1.0).abs() < 1e-6);
+    }
+}
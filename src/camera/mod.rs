@@ -0,0 +1,577 @@
+use crate::{
+    camera::{
+        bookmarks::{CameraBookmarks, init_bookmark_input_ids, use_bookmarks},
+        effects::{
+            CameraEffect, CameraEffects, apply_camera_effects, fade_screen_flashes,
+            receive_camera_effects, remove_camera_effects,
+        },
+        settings::{CameraSettings, load_camera_settings},
+        viewport::{PrimaryCamera, toggle_minimap, update_viewports},
+    },
+    input::{InputMapping, InputState, axis::AxisMapping},
+    modding::{
+        PostModLoad,
+        registry::{Id, Registry},
+    },
+    selection::{Selected, selection_bounds},
+};
+use avian2d::prelude::*;
+use bevy::{camera::CameraUpdateSystems, prelude::*, transform::TransformSystems};
+
+pub mod bookmarks;
+pub mod effects;
+pub mod settings;
+pub mod viewport;
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraSettings>()
+            .init_resource::<CameraBookmarks>()
+            .add_message::<CameraEffect>()
+            .add_systems(
+                PostModLoad,
+                (
+                    init_camera_input_ids,
+                    init_bookmark_input_ids,
+                    load_camera_settings,
+                ),
+            )
+            .add_systems(Startup, setup)
+            .add_systems(
+                Update,
+                (
+                    toggle_minimap,
+                    apply_camera_settings.run_if(resource_changed::<CameraSettings>),
+                    update_viewports,
+                    camera_control,
+                    focus_selection,
+                    use_bookmarks,
+                    camera_follow,
+                    clamp_to_bounds,
+                )
+                    .chain(),
+            )
+            .add_systems(First, remove_camera_effects)
+            .add_systems(
+                Update,
+                (receive_camera_effects, fade_screen_flashes).chain(),
+            )
+            .add_systems(
+                PostUpdate,
+                // Before the projection is updated, so the zoom punch reaches this frame's view
+                apply_camera_effects
+                    .before(CameraUpdateSystems)
+                    .before(TransformSystems::Propagate),
+            );
+    }
+}
+
+#[derive(Debug, Resource)]
+struct CameraInputIds {
+    up: Id,
+    down: Id,
+    left: Id,
+    right: Id,
+    speed: Id,
+    zoom_in: Id,
+    zoom_out: Id,
+    zoom: Id,
+    pan: Id,
+    focus: Id,
+    follow: Id,
+    minimap: Id,
+}
+
+fn init_camera_input_ids(
+    mut commands: Commands,
+    inputs: Res<Registry<InputMapping>>,
+    axes: Res<Registry<AxisMapping>>,
+) {
+    let ids = CameraInputIds {
+        up: inputs.lookup("base::input::up").unwrap(),
+        down: inputs.lookup("base::input::down").unwrap(),
+        left: inputs.lookup("base::input::left").unwrap(),
+        right: inputs.lookup("base::input::right").unwrap(),
+        speed: inputs.lookup("base::input::speed").unwrap(),
+        zoom_in: inputs.lookup("base::input::zoom_in").unwrap(),
+        zoom_out: inputs.lookup("base::input::zoom_out").unwrap(),
+        zoom: axes.lookup("base::input::zoom").unwrap(),
+        pan: inputs.lookup("base::input::pan").unwrap(),
+        focus: inputs.lookup("base::input::focus").unwrap(),
+        follow: inputs.lookup("base::input::follow").unwrap(),
+        minimap: inputs.lookup("base::input::minimap").unwrap(),
+    };
+    commands.insert_resource(ids);
+}
+
+/// Zoom limits and easing for a camera
+#[derive(Debug, Clone, Component)]
+pub struct CameraZoom {
+    /// The scale the camera is easing towards
+    pub target: f32,
+    pub min: f32,
+    pub max: f32,
+    /// Roughly how many seconds the camera takes to reach the target scale. Zero snaps to it.
+    pub smoothing: f32,
+    /// The scale multiplier for one step of zooming in
+    pub step: f32,
+    /// Whether the zoom stays centred on the cursor, as it does when the player zooms
+    anchored: bool,
+}
+
+impl Default for CameraZoom {
+    fn default() -> Self {
+        Self {
+            target: 1.0,
+            min: 0.05,
+            max: 16.0,
+            smoothing: 0.08,
+            step: 0.75,
+            anchored: false,
+        }
+    }
+}
+
+impl CameraZoom {
+    /// Zooms in by `steps`, or out if negative, with each step scaling by the same ratio
+    pub fn zoom(&mut self, steps: f32) {
+        if steps != 0.0 {
+            self.target = (self.target * self.step.powf(steps)).clamp(self.min, self.max);
+            self.anchored = true;
+        }
+    }
+
+    /// Takes the limits, easing and step size the player set
+    pub fn apply_settings(&mut self, settings: &CameraSettings) {
+        self.min = settings.min_scale;
+        self.max = settings.max_scale;
+        self.smoothing = settings.zoom_smoothing;
+        self.step = 1.0 - settings.zoom_factor;
+        self.target = self.target.clamp(self.min, self.max);
+    }
+
+    /// Eases to `scale` around the centre of the screen
+    pub fn set_target(&mut self, scale: f32) {
+        self.target = scale.clamp(self.min, self.max);
+        self.anchored = false;
+    }
+
+    /// Returns the scale to use this frame, moving from `scale` towards the target
+    pub fn ease(&self, scale: f32, delta_secs: f32) -> f32 {
+        if self.smoothing <= 0.0 {
+            return self.target;
+        }
+        let t = 1.0 - (-delta_secs / self.smoothing).exp();
+        // Ease in log space, so zooming in and out feel the same
+        let scale = (scale.ln() + (self.target.ln() - scale.ln()) * t).exp();
+        if (scale / self.target - 1.0).abs() < 1e-4 {
+            self.target
+        } else {
+            scale
+        }
+    }
+}
+
+/// Moves a camera after an entity, or to a point
+#[derive(Debug, Clone, Component)]
+pub struct CameraFollow {
+    /// The entity being followed
+    pub target: Option<Entity>,
+    /// A point the camera is moving to, such as after focusing on the selection
+    pub point: Option<Vec2>,
+    /// Roughly how many seconds the camera takes to catch up. Zero keeps up exactly.
+    pub damping: f32,
+    /// Half the size of the area around the centre of the screen the target can move within
+    /// without the camera following, in world units at a scale of one
+    pub deadzone: Vec2,
+    /// How many seconds ahead of a moving target the camera aims
+    pub look_ahead: f32,
+}
+
+impl Default for CameraFollow {
+    fn default() -> Self {
+        Self {
+            target: None,
+            point: None,
+            damping: 0.25,
+            deadzone: Vec2::new(16.0, 12.0),
+            look_ahead: 0.3,
+        }
+    }
+}
+
+impl CameraFollow {
+    pub fn follow(&mut self, target: Entity) {
+        self.target = Some(target);
+        self.point = None;
+    }
+
+    pub fn move_to(&mut self, point: Vec2) {
+        self.target = None;
+        self.point = Some(point);
+    }
+
+    pub fn stop(&mut self) {
+        self.target = None;
+        self.point = None;
+    }
+}
+
+/// The speed a camera keeps gliding at after a drag pan
+#[derive(Debug, Default, Clone, Component)]
+pub struct CameraInertia {
+    pub velocity: Vec2,
+}
+
+/// How much larger than the selection the view is when focusing on it
+const FOCUS_MARGIN: f32 = 1.5;
+
+fn setup(mut commands: Commands, settings: Res<CameraSettings>) {
+    commands.spawn((
+        Camera2d::default(),
+        PrimaryCamera,
+        // Otherwise the UI would be drawn by whichever camera is on top, such as the minimap
+        IsDefaultUiCamera,
+        CameraZoom::default(),
+        CameraFollow::default(),
+        CameraInertia::default(),
+        CameraEffects::default(),
+        Projection::Orthographic(OrthographicProjection {
+            scaling_mode: bevy::camera::ScalingMode::FixedVertical {
+                viewport_height: settings.viewport_height,
+            },
+            ..OrthographicProjection::default_2d()
+        }),
+    ));
+}
+
+fn apply_camera_settings(
+    mut query: Query<(&mut Projection, &mut CameraZoom, Has<PrimaryCamera>), With<Camera>>,
+    settings: Res<CameraSettings>,
+) {
+    for (mut projection, mut zoom, is_primary) in query.iter_mut() {
+        zoom.apply_settings(&settings);
+
+        // Other cameras size their own views, such as a minimap showing the whole world
+        if is_primary && let Projection::Orthographic(ortho) = projection.as_mut() {
+            ortho.scaling_mode = bevy::camera::ScalingMode::FixedVertical {
+                viewport_height: settings.viewport_height,
+            };
+        }
+    }
+}
+
+/// Finds the camera the player is controlling: the one under the cursor if it has camera
+/// controls, or else the primary camera
+fn controlled_camera(
+    input: &InputState,
+    controllable: impl Fn(Entity) -> bool,
+    primary: &Query<Entity, With<PrimaryCamera>>,
+) -> Option<Entity> {
+    input
+        .cursor_camera()
+        .filter(|&entity| controllable(entity))
+        .or_else(|| primary.iter().find(|&entity| controllable(entity)))
+}
+
+fn camera_control(
+    mut query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Projection,
+            &mut CameraZoom,
+            &mut CameraFollow,
+            &mut CameraInertia,
+        ),
+        With<Camera>,
+    >,
+    primary: Query<Entity, With<PrimaryCamera>>,
+    mut dragging: Local<Option<Entity>>,
+    input: Res<InputState>,
+    ids: Res<CameraInputIds>,
+    settings: Res<CameraSettings>,
+    time: Res<Time>,
+) {
+    // A drag stays with the camera it started on, even if the cursor leaves its viewport
+    let controlled = controlled_camera(&input, |entity| query.contains(entity), &primary);
+    if !input.pressed(ids.pan) {
+        *dragging = None;
+    } else if dragging.is_none() {
+        *dragging = controlled;
+    }
+    let controlled = dragging.or(controlled);
+
+    for (entity, mut transform, projection, mut zoom, mut follow, mut inertia) in query.iter_mut() {
+        let Projection::Orthographic(ortho) = projection.into_inner() else {
+            continue;
+        };
+        let is_controlled = controlled == Some(entity);
+        // The cursor's world position and gestures are measured through the camera under it
+        let is_hovered = input.cursor_camera() == Some(entity);
+
+        // Zoom first, as the cursor's world position was measured before the camera moved this
+        // frame. Cameras that aren't being controlled still finish easing and gliding.
+        if is_controlled {
+            zoom.zoom(zoom_steps(&input, &ids));
+        }
+        let anchor = input.mouse_world().filter(|_| is_hovered);
+        ease_zoom(&mut transform, ortho, &zoom, anchor, &time);
+
+        if is_controlled {
+            key_pan(&input, &ids, &mut transform, ortho, &settings, &time);
+        }
+
+        let drag = (is_controlled && input.pressed(ids.pan)).then(|| {
+            if is_hovered {
+                input.mouse_world_delta()
+            } else {
+                Vec2::ZERO
+            }
+        });
+        let gesture = if is_hovered {
+            input.gesture_world_delta()
+        } else {
+            Vec2::ZERO
+        };
+        drag_pan(
+            drag,
+            gesture,
+            &mut transform,
+            &mut inertia,
+            &settings,
+            &time,
+        );
+
+        // Panning by hand takes the camera back from following
+        let panned = input.analog_vec2(ids.right, ids.left, ids.up, ids.down) != Vec2::ZERO
+            || input.pressed(ids.pan);
+        if (is_controlled && panned) || gesture != Vec2::ZERO {
+            follow.stop();
+        }
+        if follow.target.is_some() || follow.point.is_some() {
+            inertia.velocity = Vec2::ZERO;
+        }
+    }
+}
+
+fn key_pan(
+    input: &InputState,
+    ids: &CameraInputIds,
+    transform: &mut Transform,
+    ortho: &OrthographicProjection,
+    settings: &CameraSettings,
+    time: &Time,
+) {
+    // Sticks pan proportionally, while diagonal keys are kept to the same speed as straight ones
+    let dir = input
+        .analog_vec2(ids.right, ids.left, ids.up, ids.down)
+        .clamp_length_max(1.0);
+
+    if dir == Vec2::ZERO {
+        return;
+    }
+
+    let mut speed = settings.pan_speed;
+    if input.pressed(ids.speed) {
+        speed *= settings.fast_pan_multiplier;
+    }
+
+    transform.translation += dir.extend(0.0) * speed * ortho.scale * time.delta_secs();
+}
+
+fn zoom_steps(input: &InputState, ids: &CameraInputIds) -> f32 {
+    let mut steps = input.axis_value(ids.zoom);
+    if input.triggered(ids.zoom_in) {
+        steps += 1.0;
+    }
+    if input.triggered(ids.zoom_out) {
+        steps -= 1.0;
+    }
+    steps
+}
+
+fn ease_zoom(
+    transform: &mut Transform,
+    ortho: &mut OrthographicProjection,
+    zoom: &CameraZoom,
+    cursor: Option<Vec2>,
+    time: &Time,
+) {
+    let scale = zoom.ease(ortho.scale, time.delta_secs());
+    if scale == ortho.scale {
+        return;
+    }
+
+    // Keep the point under the cursor fixed, or zoom around the centre without one
+    if zoom.anchored
+        && let Some(anchor) = cursor
+    {
+        let anchor = anchor.extend(transform.translation.z);
+        transform.translation = anchor + (transform.translation - anchor) * (scale / ortho.scale);
+    }
+    ortho.scale = scale;
+}
+
+/// Moves the camera by how far the cursor was dragged across the world, if it is being dragged,
+/// or keeps it gliding after a drag
+fn drag_pan(
+    drag: Option<Vec2>,
+    gesture: Vec2,
+    transform: &mut Transform,
+    inertia: &mut CameraInertia,
+    settings: &CameraSettings,
+    time: &Time,
+) {
+    let delta_secs = time.delta_secs();
+
+    // Keep the point under the cursor or fingers fixed in place
+    transform.translation -= gesture.extend(0.0);
+    if let Some(drag) = drag {
+        let moved = -drag;
+        transform.translation += moved.extend(0.0);
+
+        // Average over a few frames, so a drag that stops before release doesn't fling
+        if delta_secs > 0.0 {
+            inertia.velocity = inertia.velocity.lerp(moved / delta_secs, 0.5);
+        }
+        return;
+    }
+
+    if !settings.inertia {
+        inertia.velocity = Vec2::ZERO;
+        return;
+    }
+
+    transform.translation += (inertia.velocity * delta_secs).extend(0.0);
+    inertia.velocity *= (-settings.inertia_friction * delta_secs).exp();
+    if inertia.velocity.length_squared() < 1e-4 {
+        inertia.velocity = Vec2::ZERO;
+    }
+}
+
+/// Follows the selection, or moves to frame it
+fn focus_selection(
+    mut query: Query<(&Projection, &mut CameraZoom, &mut CameraFollow), With<Camera>>,
+    primary: Query<Entity, With<PrimaryCamera>>,
+    input: Res<InputState>,
+    ids: Res<CameraInputIds>,
+    selected: Query<(Entity, &ColliderAabb), With<Selected>>,
+) {
+    if !input.just_pressed(ids.follow) && !input.just_pressed(ids.focus) {
+        return;
+    }
+    let Some(camera) = controlled_camera(&input, |entity| query.contains(entity), &primary) else {
+        return;
+    };
+    let Ok((projection, mut zoom, mut follow)) = query.get_mut(camera) else {
+        return;
+    };
+
+    if input.just_pressed(ids.follow) {
+        match selected.iter().next() {
+            Some((entity, _)) if follow.target != Some(entity) => follow.follow(entity),
+            _ => follow.stop(),
+        }
+    }
+
+    if input.just_pressed(ids.focus) {
+        let Some(bounds) = selection_bounds(selected.iter().map(|(_, aabb)| aabb)) else {
+            return;
+        };
+
+        follow.move_to(bounds.center());
+        if let Projection::Orthographic(ortho) = projection {
+            // The size of the view at a scale of one
+            let view = ortho.area.size() / ortho.scale;
+            zoom.set_target((bounds.size() * FOCUS_MARGIN / view).max_element());
+        }
+    }
+}
+
+fn camera_follow(
+    mut query: Query<(&mut Transform, &Projection, &mut CameraFollow), With<Camera>>,
+    targets: Query<(&GlobalTransform, Option<&LinearVelocity>)>,
+    time: Res<Time>,
+) {
+    for (mut transform, projection, mut follow) in query.iter_mut() {
+        let scale = match projection {
+            Projection::Orthographic(ortho) => ortho.scale,
+            _ => 1.0,
+        };
+
+        let (goal, deadzone) = match (follow.target, follow.point) {
+            (Some(target), _) => {
+                let Ok((global, velocity)) = targets.get(target) else {
+                    // The target was despawned
+                    follow.stop();
+                    continue;
+                };
+                let velocity = velocity.map_or(Vec2::ZERO, |v| v.0);
+                let goal = global.translation().truncate() + velocity * follow.look_ahead;
+                (goal, follow.deadzone * scale)
+            }
+            (None, Some(point)) => (point, Vec2::ZERO),
+            (None, None) => continue,
+        };
+
+        // Only the part of the offset outside the deadzone moves the camera
+        let offset = goal - transform.translation.truncate();
+        let excess = offset - offset.clamp(-deadzone, deadzone);
+
+        let t = if follow.damping > 0.0 {
+            1.0 - (-time.delta_secs() / follow.damping).exp()
+        } else {
+            1.0
+        };
+        transform.translation += (excess * t).extend(0.0);
+
+        if follow.target.is_none() && excess.length() < 0.01 * scale {
+            follow.point = None;
+        }
+    }
+}
+
+/// Keeps the view inside the world bounds, or centred on them if it is too large to fit
+fn clamp_to_bounds(
+    mut query: Query<(&mut Transform, &Projection, &mut CameraInertia), With<Camera>>,
+    settings: Res<CameraSettings>,
+) {
+    let Some(bounds) = settings.bounds else {
+        return;
+    };
+
+    for (mut transform, projection, mut inertia) in query.iter_mut() {
+        let half_size = match projection {
+            Projection::Orthographic(ortho) => ortho.area.half_size(),
+            _ => Vec2::ZERO,
+        };
+        let min = bounds.min + half_size;
+        let max = bounds.max - half_size;
+        let centre = bounds.center();
+
+        let position = transform.translation.truncate();
+        let clamp = |value: f32, min: f32, max: f32, centre: f32| {
+            if min <= max {
+                value.clamp(min, max)
+            } else {
+                centre
+            }
+        };
+        let clamped = Vec2::new(
+            clamp(position.x, min.x, max.x, centre.x),
+            clamp(position.y, min.y, max.y, centre.y),
+        );
+
+        // Stop gliding into the edge
+        if clamped.x != position.x {
+            inertia.velocity.x = 0.0;
+        }
+        if clamped.y != position.y {
+            inertia.velocity.y = 0.0;
+        }
+        transform.translation = clamped.extend(transform.translation.z);
+    }
+}
@@ -0,0 +1,103 @@
+use std::{fs, io, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The file the player's camera settings are loaded from
+pub const CAMERA_CONFIG_PATH: &str = "config/camera.toml";
+
+/// Tuning for the camera controls. Mods can change these while loading, and any set in the
+/// player's config file override them.
+#[derive(Debug, Clone, PartialEq, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraSettings {
+    /// How fast the keys pan the camera, in world units per second at a scale of one
+    pub pan_speed: f32,
+    /// How many times faster the camera pans while the speed action is held
+    pub fast_pan_multiplier: f32,
+    /// How much of the view one step of zooming in removes
+    pub zoom_factor: f32,
+    pub min_scale: f32,
+    pub max_scale: f32,
+    /// Roughly how many seconds zooming takes to settle. Zero snaps straight to the new scale.
+    pub zoom_smoothing: f32,
+    /// The height of the view in world units at a scale of one
+    pub viewport_height: f32,
+    /// The area of the world the view can't leave
+    pub bounds: Option<Rect>,
+    /// Whether the camera keeps gliding after a drag pan is let go
+    pub inertia: bool,
+    /// How quickly a glide slows down, as a rate per second
+    pub inertia_friction: f32,
+    /// How far the camera shakes at full trauma, in world units at a scale of one
+    pub shake_offset: f32,
+    /// How far the camera tilts at full trauma, in radians
+    pub shake_angle: f32,
+    /// How much trauma wears off each second
+    pub trauma_decay: f32,
+    /// Scales the strength of shakes, zoom punches and flashes. Zero turns them off.
+    pub effects_intensity: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            pan_speed: 128.0,
+            fast_pan_multiplier: 4.0,
+            zoom_factor: 0.25,
+            min_scale: 0.05,
+            max_scale: 16.0,
+            zoom_smoothing: 0.08,
+            viewport_height: 256.0,
+            bounds: None,
+            inertia: true,
+            inertia_friction: 5.0,
+            shake_offset: 8.0,
+            shake_angle: 0.05,
+            trauma_decay: 1.5,
+            effects_intensity: 1.0,
+        }
+    }
+}
+
+impl CameraSettings {
+    /// Overrides the settings with any given in the file at `path`, leaving the rest as they are
+    pub fn load_overrides(&mut self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+
+        let contents = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return,
+            Err(e) => {
+                error!("unable to read {}: {}", path.display(), e);
+                return;
+            }
+        };
+
+        let overrides: toml::Table = match toml::from_str(&contents) {
+            Ok(t) => t,
+            Err(e) => {
+                error!("error parsing {}:\n{}", path.display(), e);
+                return;
+            }
+        };
+
+        let mut table = match toml::Table::try_from(&*self) {
+            Ok(t) => t,
+            Err(e) => {
+                error!("unable to serialise camera settings: {}", e);
+                return;
+            }
+        };
+        table.extend(overrides);
+
+        match table.try_into() {
+            Ok(settings) => *self = settings,
+            Err(e) => error!("error parsing {}:\n{}", path.display(), e),
+        }
+    }
+}
+
+pub(super) fn load_camera_settings(mut settings: ResMut<CameraSettings>) {
+    settings.load_overrides(CAMERA_CONFIG_PATH);
+}
@@ -0,0 +1,139 @@
+use bevy::{
+    camera::{NormalizedRenderTarget, ScalingMode, Viewport},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::{
+    camera::{CameraFollow, CameraInertia, CameraInputIds, CameraZoom, settings::CameraSettings},
+    input::InputState,
+};
+
+/// Marks the main camera, which takes the player's input whenever the cursor isn't over another
+/// camera that can be controlled
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct PrimaryCamera;
+
+/// Draws a camera to part of its window, such as for a minimap or an inset view. The area is
+/// given as fractions of the window's size from its top left corner, so it keeps its place when
+/// the window is resized.
+///
+/// The minimap is the only inset the game spawns for now. A follow inset and a slow motion
+/// replay view are left for later, as the replay view needs past world states to show.
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub struct CameraViewport {
+    pub rect: Rect,
+}
+
+impl CameraViewport {
+    pub fn new(rect: Rect) -> Self {
+        Self { rect }
+    }
+
+    /// An area `size` across, as a fraction of the window, tucked into a corner `margin` from
+    /// the edges. `corner` is a fraction of the window, so (1, 0) is the top right.
+    pub fn inset(corner: Vec2, size: Vec2, margin: f32) -> Self {
+        let corner = corner.clamp(Vec2::ZERO, Vec2::ONE);
+        let min = (Vec2::ONE - size - Vec2::splat(margin * 2.0)).max(Vec2::ZERO) * corner
+            + Vec2::splat(margin);
+        Self {
+            rect: Rect::from_corners(min, min + size),
+        }
+    }
+}
+
+/// Marks the minimap, an inset camera following the primary camera that shows more of the world
+/// around it. It can be panned and zoomed like the primary camera while the cursor is over it.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct Minimap;
+
+/// How much more of the world the minimap shows than the primary camera, at the same scale
+const MINIMAP_ZOOM: f32 = 8.0;
+
+/// The size of the minimap as a fraction of the window, and its distance from the window's edges
+const MINIMAP_SIZE: Vec2 = Vec2::splat(0.25);
+const MINIMAP_MARGIN: f32 = 0.02;
+
+/// Shows or hides the minimap in the top right corner of the window
+pub(super) fn toggle_minimap(
+    mut commands: Commands,
+    minimap: Query<Entity, With<Minimap>>,
+    primary: Query<(Entity, &Transform), With<PrimaryCamera>>,
+    input: Res<InputState>,
+    ids: Res<CameraInputIds>,
+    settings: Res<CameraSettings>,
+) {
+    if !input.just_pressed(ids.minimap) {
+        return;
+    }
+    if let Ok(entity) = minimap.single() {
+        commands.entity(entity).despawn();
+        return;
+    }
+    let Ok((primary, transform)) = primary.single() else {
+        return;
+    };
+    let mut zoom = CameraZoom::default();
+    zoom.apply_settings(&settings);
+
+    commands.spawn((
+        Camera2d,
+        Camera {
+            order: 1,
+            ..default()
+        },
+        Minimap,
+        CameraViewport::inset(Vec2::new(1.0, 0.0), MINIMAP_SIZE, MINIMAP_MARGIN),
+        zoom,
+        CameraFollow {
+            target: Some(primary),
+            damping: 0.0,
+            deadzone: Vec2::ZERO,
+            look_ahead: 0.0,
+            ..default()
+        },
+        CameraInertia::default(),
+        Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::FixedVertical {
+                viewport_height: settings.viewport_height * MINIMAP_ZOOM,
+            },
+            ..OrthographicProjection::default_2d()
+        }),
+        *transform,
+    ));
+}
+
+/// Resizes the viewports of cameras drawing to part of a window to match the window
+pub(super) fn update_viewports(
+    mut cameras: Query<(&mut Camera, &CameraViewport)>,
+    windows: Query<&Window>,
+    primary: Query<Entity, With<PrimaryWindow>>,
+) {
+    let primary = primary.single().ok();
+
+    for (mut camera, viewport) in cameras.iter_mut() {
+        let Some(NormalizedRenderTarget::Window(target)) = camera.target.normalize(primary) else {
+            continue;
+        };
+        let Ok(window) = windows.get(target.entity()) else {
+            continue;
+        };
+
+        let size = window.physical_size().as_vec2();
+        let min = (viewport.rect.min.clamp(Vec2::ZERO, Vec2::ONE) * size).round();
+        let max = (viewport.rect.max.clamp(Vec2::ZERO, Vec2::ONE) * size).round();
+        let physical_position = min.as_uvec2();
+        let physical_size = (max - min).max(Vec2::ONE).as_uvec2();
+
+        let unchanged = camera.viewport.as_ref().is_some_and(|current| {
+            current.physical_position == physical_position && current.physical_size == physical_size
+        });
+        if !unchanged {
+            camera.viewport = Some(Viewport {
+                physical_position,
+                physical_size,
+                ..default()
+            });
+        }
+    }
+}
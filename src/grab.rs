@@ -0,0 +1,248 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    Cursor,
+    history::{
+        self, EditGroup,
+        edits::{FreezeEdit, TransformEdit},
+    },
+    input::{InputMapping, InputState},
+    modding::{
+        PostModLoad,
+        registry::{Id, Registry},
+    },
+    tool::ToolSystems,
+};
+
+/// Lets the player pick up objects with the drag tool, throw them and freeze them in place.
+pub struct GrabPlugin;
+
+impl Plugin for GrabPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GrabState>()
+            .add_systems(PostModLoad, init_grab_input_ids)
+            .add_systems(Update, (drag_grab, release_grab).chain().after(ToolSystems));
+    }
+}
+
+/// How stretchy the joint holding a grabbed object is. Higher values let heavy objects sag
+/// further behind the cursor.
+const GRAB_COMPLIANCE: f32 = 1e-6;
+
+/// The fastest an object can be thrown, in world units per second
+const MAX_THROW_SPEED: f32 = 2048.0;
+
+/// Marks a body frozen in place by the player, with the kind of body it was before
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Frozen {
+    pub body: RigidBody,
+}
+
+/// The object held by the cursor
+#[derive(Debug, Default, Resource)]
+pub struct GrabState {
+    grab: Option<Grab>,
+}
+
+impl GrabState {
+    /// Returns the entity being held
+    pub fn grabbed(&self) -> Option<Entity> {
+        self.grab.as_ref().map(|grab| grab.body)
+    }
+}
+
+#[derive(Debug)]
+struct Grab {
+    body: Entity,
+    joint: Entity,
+    /// Where the object was when picked up, and how it was frozen if it was
+    from: Transform,
+    thawed: Option<Frozen>,
+    /// How fast the cursor has been moving through the world, which the object is thrown at
+    velocity: Vec2,
+    /// Where the cursor was last frame
+    last_point: Vec2,
+}
+
+impl Grab {
+    /// The edit for having moved the object since it was picked up, which puts it back where it
+    /// was, frozen if it was
+    fn edit(&self, transform: &Transform) -> EditGroup {
+        let mut edit = EditGroup::new();
+        if let Some(frozen) = &self.thawed {
+            edit = edit.with(FreezeEdit::thawed(self.body, frozen));
+        }
+        edit.with(TransformEdit::new([(self.body, self.from, *transform)]))
+    }
+}
+
+#[derive(Debug, Resource)]
+pub(crate) struct GrabInputIds {
+    select: Id,
+    freeze: Id,
+}
+
+fn init_grab_input_ids(mut commands: Commands, inputs: Res<Registry<InputMapping>>) {
+    let ids = GrabInputIds {
+        select: inputs.lookup("base::input::select").unwrap(),
+        freeze: inputs.lookup("base::input::freeze").unwrap(),
+    };
+    commands.insert_resource(ids);
+}
+
+/// Freezes a body in place, remembering what kind of body it was
+pub fn freeze(commands: &mut Commands, entity: Entity, body: RigidBody) {
+    commands.entity(entity).insert((
+        Frozen { body },
+        RigidBody::Static,
+        LinearVelocity::ZERO,
+        AngularVelocity::ZERO,
+    ));
+}
+
+/// Lets a frozen body move again
+pub fn thaw(commands: &mut Commands, entity: Entity, frozen: &Frozen) {
+    commands
+        .entity(entity)
+        .remove::<Frozen>()
+        .insert(frozen.body);
+}
+
+/// Attaches the object under the cursor to it at the point that was clicked. This is the drag
+/// tool's update hook.
+pub fn start_grab(
+    mut commands: Commands,
+    mut state: ResMut<GrabState>,
+    input: Res<InputState>,
+    ids: Res<GrabInputIds>,
+    spatial_query: SpatialQuery,
+    bodies: Query<(&RigidBody, &Transform, &GlobalTransform, Option<&Frozen>)>,
+    cursor: Query<Entity, With<Cursor>>,
+) {
+    if state.grab.is_some() || !input.just_pressed(ids.select) {
+        return;
+    }
+    let (Some(point), Ok(cursor)) = (input.mouse_world(), cursor.single()) else {
+        return;
+    };
+
+    // Only objects physics moves can be picked up, along with ones the player froze
+    let filter = SpatialQueryFilter::default().with_excluded_entities([cursor]);
+    let Some((body, transform, global, frozen)) = spatial_query
+        .point_intersections(point, &filter)
+        .into_iter()
+        .filter_map(|entity| Some((entity, bodies.get(entity).ok()?)))
+        .find_map(|(entity, (body, transform, global, frozen))| {
+            (*body == RigidBody::Dynamic || frozen.is_some())
+                .then_some((entity, transform, global, frozen))
+        })
+    else {
+        return;
+    };
+
+    if let Some(frozen) = frozen {
+        thaw(&mut commands, body, frozen);
+    }
+
+    let anchor = global
+        .affine()
+        .inverse()
+        .transform_point3(point.extend(0.0))
+        .truncate();
+    let joint = commands
+        .spawn((
+            DistanceJoint::new(cursor, body)
+                .with_local_anchor2(anchor)
+                .with_limits(0.0, 0.0)
+                .with_compliance(GRAB_COMPLIANCE),
+            JointCollisionDisabled,
+        ))
+        .id();
+
+    state.grab = Some(Grab {
+        body,
+        joint,
+        from: *transform,
+        thawed: frozen.copied(),
+        velocity: Vec2::ZERO,
+        last_point: point,
+    });
+}
+
+/// Keeps track of how fast the held object is being swung around
+fn drag_grab(
+    mut commands: Commands,
+    mut state: ResMut<GrabState>,
+    input: Res<InputState>,
+    bodies: Query<(), With<RigidBody>>,
+    time: Res<Time>,
+) {
+    let Some(grab) = state.grab.as_mut() else {
+        return;
+    };
+
+    // The object was despawned while held
+    if !bodies.contains(grab.body) {
+        commands.entity(grab.joint).despawn();
+        state.grab = None;
+        return;
+    }
+
+    let Some(point) = input.mouse_world() else {
+        return;
+    };
+    let delta_secs = time.delta_secs();
+    if delta_secs > 0.0 {
+        // Average over a few frames, so stopping just before letting go drops the object
+        let velocity = (point - grab.last_point) / delta_secs;
+        grab.velocity = grab.velocity.lerp(velocity, 0.5);
+    }
+    grab.last_point = point;
+}
+
+/// Throws the held object when let go, or freezes it where it is
+fn release_grab(
+    mut commands: Commands,
+    mut state: ResMut<GrabState>,
+    input: Res<InputState>,
+    ids: Res<GrabInputIds>,
+    mut bodies: Query<(&RigidBody, &Transform, &mut LinearVelocity)>,
+) {
+    let freeze = input.just_pressed(ids.freeze);
+    if !freeze && !input.just_released(ids.select) {
+        return;
+    }
+    let Some(grab) = state.grab.take() else {
+        return;
+    };
+
+    commands.entity(grab.joint).despawn();
+    let Ok((&body, transform, mut linear)) = bodies.get_mut(grab.body) else {
+        return;
+    };
+
+    let mut edit = grab.edit(transform);
+    if freeze {
+        self::freeze(&mut commands, grab.body, body);
+        edit = edit.with(FreezeEdit::frozen(grab.body, body));
+    } else {
+        linear.0 = grab.velocity.clamp_length_max(MAX_THROW_SPEED);
+    }
+    history::record(&mut commands, edit);
+}
+
+/// Lets go of the held object without throwing it, when switching away from the drag tool
+pub fn drop_grab(
+    mut commands: Commands,
+    mut state: ResMut<GrabState>,
+    transforms: Query<&Transform, With<RigidBody>>,
+) {
+    let Some(grab) = state.grab.take() else {
+        return;
+    };
+    commands.entity(grab.joint).despawn();
+    if let Ok(transform) = transforms.get(grab.body) {
+        history::record(&mut commands, grab.edit(transform));
+    }
+}
@@ -0,0 +1,302 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    grab::{Frozen, freeze, thaw},
+    history::{Edit, EntityMap},
+    joint::{Connection, JointSnapshot, despawn_connection},
+    modding::registry::Registry,
+    spawn::{ObjectSnapshot, Spawnable, Spawned},
+};
+
+/// Objects spawned or despawned by the player, along with any joints they had
+pub struct ObjectsEdit {
+    entities: Vec<Entity>,
+    /// Whether the edit brought the objects into the world, rather than taking them out
+    spawned: bool,
+    /// The objects and joints while they are out of the world
+    removed: Option<Removed>,
+}
+
+struct Removed {
+    objects: Vec<(Entity, ObjectSnapshot)>,
+    joints: Vec<(Entity, JointSnapshot)>,
+}
+
+impl ObjectsEdit {
+    pub fn spawned(entities: Vec<Entity>) -> Self {
+        Self {
+            entities,
+            spawned: true,
+            removed: None,
+        }
+    }
+
+    pub fn despawned(entities: Vec<Entity>) -> Self {
+        Self {
+            entities,
+            spawned: false,
+            removed: None,
+        }
+    }
+
+    fn take_out(&mut self, world: &mut World, entities: &EntityMap) {
+        let objects: Vec<Entity> = self.entities.iter().map(|&e| entities.get(e)).collect();
+
+        let mut joints = world.query::<(Entity, &Connection)>();
+        let joints: Vec<(Entity, Connection)> = joints
+            .iter(world)
+            .filter(|(_, connection)| {
+                objects.contains(&connection.body1) || objects.contains(&connection.body2)
+            })
+            .map(|(entity, connection)| (entity, *connection))
+            .collect();
+
+        let removed = Removed {
+            objects: objects
+                .iter()
+                .filter_map(|&entity| Some((entity, ObjectSnapshot::capture(world, entity)?)))
+                .collect(),
+            joints: joints
+                .iter()
+                .filter_map(|&(entity, _)| Some((entity, JointSnapshot::capture(world, entity)?)))
+                .collect(),
+        };
+
+        let mut commands = world.commands();
+        for (entity, connection) in joints {
+            despawn_connection(&mut commands, entity, &connection);
+        }
+        for entity in objects {
+            commands.entity(entity).try_despawn();
+        }
+        world.flush();
+
+        self.removed = Some(removed);
+    }
+
+    fn put_back(&mut self, world: &mut World, entities: &mut EntityMap) {
+        let Some(removed) = self.removed.take() else {
+            return;
+        };
+
+        world.resource_scope(|world, registry: Mut<Registry<Spawnable>>| {
+            let assets = world.resource::<AssetServer>().clone();
+            let mut commands = world.commands();
+
+            for (old, object) in &removed.objects {
+                if let Some(new) = object.spawn(&mut commands, &registry, &assets) {
+                    entities.insert(*old, new);
+                }
+            }
+            // The joints may connect to objects outside the edit, as well as those in it
+            for (old, joint) in &removed.joints {
+                let new = joint.spawn(&mut commands, |body| entities.get(body));
+                entities.insert(*old, new);
+            }
+        });
+        world.flush();
+    }
+}
+
+impl Edit for ObjectsEdit {
+    fn undo(&mut self, world: &mut World, entities: &mut EntityMap) {
+        if self.spawned {
+            self.take_out(world, entities);
+        } else {
+            self.put_back(world, entities);
+        }
+    }
+
+    fn redo(&mut self, world: &mut World, entities: &mut EntityMap) {
+        if self.spawned {
+            self.put_back(world, entities);
+        } else {
+            self.take_out(world, entities);
+        }
+    }
+}
+
+/// A joint placed by the player
+pub struct JointEdit {
+    joint: Entity,
+    /// The joint while it is out of the world
+    removed: Option<JointSnapshot>,
+}
+
+impl JointEdit {
+    pub fn placed(joint: Entity) -> Self {
+        Self {
+            joint,
+            removed: None,
+        }
+    }
+}
+
+impl Edit for JointEdit {
+    fn undo(&mut self, world: &mut World, entities: &mut EntityMap) {
+        let joint = entities.get(self.joint);
+        let Some(snapshot) = JointSnapshot::capture(world, joint) else {
+            // The joint broke since it was placed
+            return;
+        };
+        despawn_connection(&mut world.commands(), joint, &snapshot.connection);
+        world.flush();
+        self.removed = Some(snapshot);
+    }
+
+    fn redo(&mut self, world: &mut World, entities: &mut EntityMap) {
+        let Some(snapshot) = self.removed.take() else {
+            return;
+        };
+        let joint = snapshot.spawn(&mut world.commands(), |body| entities.get(body));
+        world.flush();
+        entities.insert(self.joint, joint);
+    }
+}
+
+/// Objects moved, turned or resized by the player, with their transforms before and after
+pub struct TransformEdit {
+    changes: Vec<(Entity, Transform, Transform)>,
+}
+
+impl TransformEdit {
+    /// Records the changes from `before` to `after`, leaving out anything that didn't move
+    pub fn new(changes: impl IntoIterator<Item = (Entity, Transform, Transform)>) -> Self {
+        Self {
+            changes: changes
+                .into_iter()
+                .filter(|(_, before, after)| before != after)
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    fn apply(&self, world: &mut World, entities: &EntityMap, after: bool) {
+        for &(entity, before, changed) in &self.changes {
+            let transform = if after { changed } else { before };
+            set_transform(world, entities.get(entity), transform);
+        }
+    }
+}
+
+impl Edit for TransformEdit {
+    fn undo(&mut self, world: &mut World, entities: &mut EntityMap) {
+        self.apply(world, entities, false);
+    }
+
+    fn redo(&mut self, world: &mut World, entities: &mut EntityMap) {
+        self.apply(world, entities, true);
+    }
+}
+
+/// Puts a body where `transform` says, at rest, with a new collider if it changed size
+fn set_transform(world: &mut World, entity: Entity, transform: Transform) {
+    let Ok(mut entity) = world.get_entity_mut(entity) else {
+        return;
+    };
+    let resized = entity
+        .get::<Transform>()
+        .is_some_and(|current| current.scale != transform.scale);
+    let spawned = entity.get::<Spawned>().copied();
+    entity.insert(transform);
+    if entity.contains::<LinearVelocity>() {
+        entity.insert((LinearVelocity::ZERO, AngularVelocity::ZERO));
+    }
+    let entity = entity.id();
+
+    if resized && let Some(spawned) = spawned {
+        let collider = world
+            .resource::<Registry<Spawnable>>()
+            .get(spawned.0)
+            .map(|spawnable| spawnable.shape().collider());
+        if let Some(collider) = collider {
+            world.entity_mut(entity).insert(collider);
+        }
+    }
+}
+
+/// A body frozen in place or let go by the player
+pub struct FreezeEdit {
+    entity: Entity,
+    /// The kind of body it is when not frozen
+    body: RigidBody,
+    frozen: bool,
+}
+
+impl FreezeEdit {
+    pub fn frozen(entity: Entity, body: RigidBody) -> Self {
+        Self {
+            entity,
+            body,
+            frozen: true,
+        }
+    }
+
+    pub fn thawed(entity: Entity, frozen: &Frozen) -> Self {
+        Self {
+            entity,
+            body: frozen.body,
+            frozen: false,
+        }
+    }
+
+    fn apply(&self, world: &mut World, entities: &EntityMap, frozen: bool) {
+        let entity = entities.get(self.entity);
+        if world.get_entity(entity).is_err() {
+            return;
+        }
+        if frozen {
+            freeze(&mut world.commands(), entity, self.body);
+        } else {
+            thaw(&mut world.commands(), entity, &Frozen { body: self.body });
+        }
+        world.flush();
+    }
+}
+
+impl Edit for FreezeEdit {
+    fn undo(&mut self, world: &mut World, entities: &mut EntityMap) {
+        self.apply(world, entities, !self.frozen);
+    }
+
+    fn redo(&mut self, world: &mut World, entities: &mut EntityMap) {
+        self.apply(world, entities, self.frozen);
+    }
+}
+
+/// An object painted a new colour by the player
+pub struct PaintEdit {
+    entity: Entity,
+    before: Color,
+    after: Color,
+}
+
+impl PaintEdit {
+    pub fn new(entity: Entity, before: Color, after: Color) -> Self {
+        Self {
+            entity,
+            before,
+            after,
+        }
+    }
+
+    fn apply(&self, world: &mut World, entities: &EntityMap, color: Color) {
+        if let Some(mut sprite) = world.get_mut::<Sprite>(entities.get(self.entity)) {
+            sprite.color = color;
+        }
+    }
+}
+
+impl Edit for PaintEdit {
+    fn undo(&mut self, world: &mut World, entities: &mut EntityMap) {
+        self.apply(world, entities, self.before);
+    }
+
+    fn redo(&mut self, world: &mut World, entities: &mut EntityMap) {
+        self.apply(world, entities, self.after);
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::{HashMap, VecDeque};
+
+use bevy::prelude::*;
+
+use crate::{
+    input::{InputMapping, InputState},
+    modding::{
+        PostModLoad,
+        registry::{Id, Registry},
+    },
+    tool::ToolSystems,
+};
+
+pub mod edits;
+
+/// Keeps the edits the player makes to the world, so they can be undone and redone.
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(History::new(HISTORY_SIZE))
+            .add_systems(PostModLoad, init_history_input_ids)
+            .add_systems(Update, undo_redo.before(ToolSystems));
+    }
+}
+
+/// The most edits that can be undone
+const HISTORY_SIZE: usize = 256;
+
+/// Something the player did to the world, which can be undone and redone
+pub trait Edit: Send + Sync + 'static {
+    fn undo(&mut self, world: &mut World, entities: &mut EntityMap);
+    fn redo(&mut self, world: &mut World, entities: &mut EntityMap);
+}
+
+/// Several edits made together, which are undone and redone as one
+#[derive(Default)]
+pub struct EditGroup {
+    edits: Vec<Box<dyn Edit>>,
+}
+
+impl EditGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, edit: impl Edit) -> Self {
+        self.edits.push(Box::new(edit));
+        self
+    }
+}
+
+impl Edit for EditGroup {
+    fn undo(&mut self, world: &mut World, entities: &mut EntityMap) {
+        for edit in self.edits.iter_mut().rev() {
+            edit.undo(world, entities);
+        }
+    }
+
+    fn redo(&mut self, world: &mut World, entities: &mut EntityMap) {
+        for edit in self.edits.iter_mut() {
+            edit.redo(world, entities);
+        }
+    }
+}
+
+/// Where entities went after being despawned and spawned again by undoing or redoing, so edits
+/// made to the old entity find the new one
+#[derive(Debug, Default)]
+pub struct EntityMap {
+    moved: HashMap<Entity, Entity>,
+}
+
+impl EntityMap {
+    /// Returns the entity `entity` is now
+    pub fn get(&self, mut entity: Entity) -> Entity {
+        while let Some(&next) = self.moved.get(&entity) {
+            entity = next;
+        }
+        entity
+    }
+
+    /// Records that `old` was spawned again as `new`
+    pub fn insert(&mut self, old: Entity, new: Entity) {
+        self.moved.insert(old, new);
+    }
+}
+
+/// The edits that can be undone, most recent last, and those undone that can be redone
+#[derive(Resource)]
+pub struct History {
+    undo: VecDeque<Box<dyn Edit>>,
+    redo: Vec<Box<dyn Edit>>,
+    entities: EntityMap,
+    size: usize,
+}
+
+impl History {
+    /// Creates a history keeping at most `size` edits
+    pub fn new(size: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            entities: EntityMap::default(),
+            size,
+        }
+    }
+
+    /// Adds an edit that was just made, forgetting anything undone and the oldest edit once full
+    pub fn push(&mut self, edit: Box<dyn Edit>) {
+        self.redo.clear();
+        self.undo.push_back(edit);
+        while self.undo.len() > self.size {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Undoes the last edit, returning whether there was one
+    pub fn undo(&mut self, world: &mut World) -> bool {
+        let Some(mut edit) = self.undo.pop_back() else {
+            return false;
+        };
+        edit.undo(world, &mut self.entities);
+        self.redo.push(edit);
+        true
+    }
+
+    /// Redoes the last edit undone, returning whether there was one
+    pub fn redo(&mut self, world: &mut World) -> bool {
+        let Some(mut edit) = self.redo.pop() else {
+            return false;
+        };
+        edit.redo(world, &mut self.entities);
+        self.undo.push_back(edit);
+        true
+    }
+
+    /// Forgets every edit, such as when a different world is loaded
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.entities = EntityMap::default();
+    }
+}
+
+/// Adds an edit the player has already made to the history, once `commands` are applied
+pub fn record(commands: &mut Commands, edit: impl Edit) {
+    commands.queue(move |world: &mut World| {
+        world.resource_mut::<History>().push(Box::new(edit));
+    });
+}
+
+/// Makes an edit to the world and adds it to the history, once `commands` are applied
+pub fn perform(commands: &mut Commands, mut edit: impl Edit) {
+    commands.queue(move |world: &mut World| {
+        world.resource_scope(|world, mut history: Mut<History>| {
+            edit.redo(world, &mut history.entities);
+            history.push(Box::new(edit));
+        });
+    });
+}
+
+#[derive(Debug, Resource)]
+struct HistoryInputIds {
+    undo: Id,
+    redo: Id,
+}
+
+fn init_history_input_ids(mut commands: Commands, inputs: Res<Registry<InputMapping>>) {
+    let ids = HistoryInputIds {
+        undo: inputs.lookup("base::input::undo").unwrap(),
+        redo: inputs.lookup("base::input::redo").unwrap(),
+    };
+    commands.insert_resource(ids);
+}
+
+fn undo_redo(world: &mut World) {
+    let ids = world.resource::<HistoryInputIds>();
+    let input = world.resource::<InputState>();
+    let (undo, redo) = (input.just_pressed(ids.undo), input.just_pressed(ids.redo));
+
+    world.resource_scope(|world, mut history: Mut<History>| {
+        // Only the most specific chord is active, so holding shift for redo leaves undo
+        // unpressed. Redo still comes first in case undo is rebound to share its keys.
+        if redo {
+            if !history.redo(world) {
+                debug!("nothing to redo");
+            }
+        } else if undo && !history.undo(world) {
+            debug!("nothing to undo");
+        }
+    });
+}
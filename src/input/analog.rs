@@ -0,0 +1,60 @@
+use bevy::{input::mouse::MouseScrollUnit, prelude::*};
+use serde::{Deserialize, Serialize};
+
+/// Settings for turning analog inputs such as sticks and triggers into action values
+#[derive(Debug, Clone, PartialEq, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalogSettings {
+    /// Raw values at or below this magnitude are treated as zero, to hide stick drift
+    pub deadzone: f32,
+    /// The value an analog binding must reach for its action to count as pressed
+    pub press_threshold: f32,
+    /// How many pixels of smooth scrolling, as from a trackpad, count as one line of a mouse wheel
+    pub pixels_per_line: f32,
+}
+
+impl AnalogSettings {
+    /// Rescales the magnitude of a raw value so it rises from zero at the edge of the deadzone to
+    /// one at full deflection
+    pub fn apply_deadzone(&self, value: f32) -> f32 {
+        let magnitude = value.abs();
+        if magnitude <= self.deadzone {
+            return 0.0;
+        }
+        ((magnitude - self.deadzone) / (1.0 - self.deadzone)).min(1.0)
+    }
+
+    /// Converts a scroll distance to lines, so wheels and trackpads scroll at similar speeds
+    pub fn scroll_lines(&self, unit: MouseScrollUnit, delta: Vec2) -> Vec2 {
+        match unit {
+            MouseScrollUnit::Line => delta,
+            MouseScrollUnit::Pixel => delta / self.pixels_per_line,
+        }
+    }
+}
+
+impl Default for AnalogSettings {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.15,
+            press_threshold: 0.5,
+            pixels_per_line: 32.0,
+        }
+    }
+}
+
+/// Which half of an axis a binding reads from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AxisDirection {
+    Positive,
+    Negative,
+}
+
+impl AxisDirection {
+    pub fn sign(self) -> f32 {
+        match self {
+            AxisDirection::Positive => 1.0,
+            AxisDirection::Negative => -1.0,
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::{HashMap, hash_map};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input::{Input, InputState, RawInputs, focus::UiCapture, touch::GestureAxis},
+    modding::registry::Id,
+};
+
+pub(super) fn update_axes(
+    state: &mut InputState,
+    map: &AxisMap,
+    raw: &RawInputs,
+    // Gives `None` for axes in inactive contexts, otherwise whether the axis acts on the world
+    liveness: impl Fn(Id) -> Option<bool>,
+) {
+    let capture = state.capture;
+    for (&id, bindings) in map.iter() {
+        let Some(world) = liveness(id) else {
+            continue;
+        };
+
+        // The binding furthest from zero decides the value
+        let value = bindings
+            .iter()
+            .filter(|binding| !world || !binding.source.blocked_by(capture))
+            .map(|binding| binding.value(raw))
+            .fold(0.0, |a: f32, b: f32| if b.abs() > a.abs() { b } else { a });
+
+        if value != 0.0 {
+            state.set_axis(id, value);
+        }
+    }
+}
+
+/// Contains the mappings of axis ids to physical inputs
+#[derive(Debug, Default, Resource)]
+pub struct AxisMap {
+    map: HashMap<Id, Vec<AxisBinding>>,
+}
+
+impl AxisMap {
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, id: Id) -> Option<&[AxisBinding]> {
+        self.map.get(&id).map(Vec::as_slice)
+    }
+
+    pub fn insert(&mut self, id: Id, bindings: Vec<AxisBinding>) {
+        self.map.insert(id, bindings);
+    }
+
+    /// Adds `binding` as an alternative binding for `id`
+    pub fn add(&mut self, id: Id, binding: AxisBinding) {
+        self.map.entry(id).or_default().push(binding);
+    }
+
+    pub fn iter<'a>(&'a self) -> hash_map::Iter<'a, Id, Vec<AxisBinding>> {
+        self.map.iter()
+    }
+}
+
+/// A physical input that produces an axis value, multiplied by `scale`.
+///
+/// Mouse sources give the change since the last frame, while gamepad axes and button composites
+/// give a position from -1 to 1.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AxisBinding {
+    #[serde(flatten)]
+    source: AxisSource,
+    #[serde(default = "default_scale", skip_serializing_if = "is_default_scale")]
+    scale: f32,
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+fn is_default_scale(scale: &f32) -> bool {
+    *scale == 1.0
+}
+
+impl AxisBinding {
+    pub fn new(source: AxisSource) -> Self {
+        Self { source, scale: 1.0 }
+    }
+
+    pub fn wheel(axis: MouseAxis) -> Self {
+        Self::new(AxisSource::MouseWheel(axis))
+    }
+
+    pub fn motion(axis: MouseAxis) -> Self {
+        Self::new(AxisSource::MouseMotion(axis))
+    }
+
+    pub fn gamepad(gamepad_axis: GamepadAxis) -> Self {
+        Self::new(AxisSource::GamepadAxis(gamepad_axis))
+    }
+
+    pub fn gesture(gesture_axis: GestureAxis) -> Self {
+        Self::new(AxisSource::Gesture(gesture_axis))
+    }
+
+    pub fn composite(positive: Input, negative: Input) -> Self {
+        Self::new(AxisSource::Composite { positive, negative })
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn inverted(self) -> Self {
+        let scale = self.scale;
+        self.with_scale(-scale)
+    }
+
+    pub fn source(&self) -> &AxisSource {
+        &self.source
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn value(&self, raw: &RawInputs) -> f32 {
+        self.source.value(raw) * self.scale
+    }
+}
+
+impl From<AxisSource> for AxisBinding {
+    fn from(value: AxisSource) -> Self {
+        Self::new(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AxisSource {
+    #[serde(rename = "wheel")]
+    MouseWheel(MouseAxis),
+    #[serde(rename = "motion")]
+    MouseMotion(MouseAxis),
+    #[serde(rename = "gamepad")]
+    GamepadAxis(GamepadAxis),
+    /// A touch screen or trackpad gesture, giving the change since the last frame
+    #[serde(rename = "gesture")]
+    Gesture(GestureAxis),
+    /// A pair of buttons, where holding `positive` gives 1 and holding `negative` gives -1
+    #[serde(rename = "buttons")]
+    Composite { positive: Input, negative: Input },
+}
+
+impl AxisSource {
+    pub fn value(&self, raw: &RawInputs) -> f32 {
+        match self {
+            AxisSource::MouseWheel(axis) => axis.of(raw.scroll),
+            AxisSource::MouseMotion(axis) => axis.of(raw.mouse_motion),
+            AxisSource::GamepadAxis(gamepad_axis) => raw
+                .gamepads
+                .iter()
+                .map(|g| {
+                    let value = g.get(*gamepad_axis).unwrap_or(0.0);
+                    raw.analog.apply_deadzone(value).copysign(value)
+                })
+                .fold(0.0, |a: f32, b: f32| if b.abs() > a.abs() { b } else { a }),
+            AxisSource::Gesture(gesture_axis) => raw.gestures.axis(*gesture_axis),
+            AxisSource::Composite { positive, negative } => {
+                positive.value(raw) - negative.value(raw)
+            }
+        }
+    }
+
+    /// Whether the source reads from a device the UI has taken
+    pub fn blocked_by(&self, capture: UiCapture) -> bool {
+        match self {
+            AxisSource::MouseWheel(_) | AxisSource::MouseMotion(_) | AxisSource::Gesture(_) => {
+                capture.pointer
+            }
+            AxisSource::GamepadAxis(_) => false,
+            AxisSource::Composite { positive, negative } => {
+                capture.blocks(positive.input_type) || capture.blocks(negative.input_type)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MouseAxis {
+    X,
+    Y,
+}
+
+impl MouseAxis {
+    fn of(self, value: Vec2) -> f32 {
+        match self {
+            MouseAxis::X => value.x,
+            MouseAxis::Y => value.y,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AxisMapping {
+    name: String,
+    default: Vec<AxisBinding>,
+    context: Option<Id>,
+}
+
+impl AxisMapping {
+    pub fn new(name: &str, default: AxisBinding) -> Self {
+        Self {
+            name: name.to_string(),
+            default: vec![default],
+            context: None,
+        }
+    }
+
+    /// Places the mapping in an input context, so it only reads while that context is active
+    pub fn in_context(mut self, context: Id) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Adds an alternative default binding
+    pub fn with_alternative(mut self, binding: AxisBinding) -> Self {
+        self.default.push(binding);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn defaults(&self) -> &[AxisBinding] {
+        &self.default
+    }
+
+    pub fn context(&self) -> Option<Id> {
+        self.context
+    }
+}
@@ -0,0 +1,146 @@
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input::{
+        Input, InputMap, InputMapping,
+        analog::AnalogSettings,
+        axis::{AxisBinding, AxisMap, AxisMapping},
+    },
+    modding::registry::Registry,
+};
+
+/// The file the user's key bindings are saved to
+pub const INPUT_CONFIG_PATH: &str = "config/input.toml";
+
+/// The user's key bindings as stored on disk, keyed by registry path.
+///
+/// Bindings for paths that are no longer registered are kept, so removing a mod does not lose
+/// its bindings.
+#[derive(Debug, Default, Clone, Resource, Serialize, Deserialize)]
+pub struct InputConfig {
+    #[serde(default)]
+    analog: AnalogSettings,
+    #[serde(default)]
+    bindings: BTreeMap<String, Vec<Input>>,
+    #[serde(default)]
+    axes: BTreeMap<String, Vec<AxisBinding>>,
+}
+
+impl InputConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the config at `path`, falling back to an empty config if it is missing or invalid
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+
+        let bytes = match fs::read(path) {
+            Ok(b) => b,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Self::new(),
+            Err(e) => {
+                error!("unable to read {}: {}", path.display(), e);
+                return Self::new();
+            }
+        };
+
+        match toml::from_slice(&bytes) {
+            Ok(c) => c,
+            Err(e) => {
+                error!("error parsing {}:\n{}", path.display(), e);
+                Self::new()
+            }
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+
+        let contents = match toml::to_string_pretty(self) {
+            Ok(c) => c,
+            Err(e) => {
+                error!("unable to serialise input config: {}", e);
+                return;
+            }
+        };
+
+        if let Some(parent) = path.parent()
+            && let Err(e) = fs::create_dir_all(parent)
+        {
+            error!("unable to create {}: {}", parent.display(), e);
+            return;
+        }
+
+        if let Err(e) = fs::write(path, contents) {
+            error!("unable to write {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn analog(&self) -> &AnalogSettings {
+        &self.analog
+    }
+
+    pub fn set_analog(&mut self, analog: AnalogSettings) {
+        self.analog = analog;
+    }
+
+    pub fn get(&self, path: &str) -> Option<&[Input]> {
+        self.bindings.get(path).map(Vec::as_slice)
+    }
+
+    pub fn insert(&mut self, path: &str, bindings: Vec<Input>) {
+        self.bindings.insert(path.to_string(), bindings);
+    }
+
+    /// Overrides the bindings in `map` with those in the config
+    pub fn apply(&self, map: &mut InputMap, registry: &Registry<InputMapping>) {
+        for (path, bindings) in self.bindings.iter() {
+            match registry.lookup(path) {
+                Some(id) => map.insert(id, bindings.clone()),
+                None => warn!("ignoring binding for unregistered input `{}`", path),
+            }
+        }
+    }
+
+    /// Updates the config with the bindings in `map` that differ from their defaults. Bindings
+    /// set back to their defaults are removed, so later changes to the defaults still apply.
+    pub fn update(&mut self, map: &InputMap, registry: &Registry<InputMapping>) {
+        for (&id, bindings) in map.iter() {
+            let (Some(path), Some(mapping)) = (registry.resolve(id), registry.get(id)) else {
+                continue;
+            };
+            if bindings.as_slice() == mapping.defaults() {
+                self.bindings.remove(path);
+            } else {
+                self.insert(path, bindings.clone());
+            }
+        }
+    }
+
+    /// Overrides the axis bindings in `map` with those in the config
+    pub fn apply_axes(&self, map: &mut AxisMap, registry: &Registry<AxisMapping>) {
+        for (path, bindings) in self.axes.iter() {
+            match registry.lookup(path) {
+                Some(id) => map.insert(id, bindings.clone()),
+                None => warn!("ignoring binding for unregistered axis `{}`", path),
+            }
+        }
+    }
+
+    /// Updates the config with the axis bindings in `map` that differ from their defaults
+    pub fn update_axes(&mut self, map: &AxisMap, registry: &Registry<AxisMapping>) {
+        for (&id, bindings) in map.iter() {
+            let (Some(path), Some(mapping)) = (registry.resolve(id), registry.get(id)) else {
+                continue;
+            };
+            if bindings.as_slice() == mapping.defaults() {
+                self.axes.remove(path);
+            } else {
+                self.axes.insert(path.to_string(), bindings.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+
+use crate::modding::registry::{Id, Registry};
+
+/// A layer of input mappings, such as the sandbox or a menu, that can be switched on and off.
+///
+/// Active contexts are checked from highest priority down, and each can let inputs through to the
+/// contexts below it, or keep them for itself.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InputContext {
+    name: String,
+    priority: i32,
+    mode: ContextMode,
+    active_by_default: bool,
+    world: bool,
+}
+
+impl InputContext {
+    pub fn new(name: &str, priority: i32, mode: ContextMode) -> Self {
+        Self {
+            name: name.to_string(),
+            priority,
+            mode,
+            active_by_default: false,
+            world: false,
+        }
+    }
+
+    /// Activates the context when the game starts
+    pub fn active_by_default(mut self) -> Self {
+        self.active_by_default = true;
+        self
+    }
+
+    /// Marks the context as acting on the game world. Its pointer inputs are ignored while the
+    /// pointer is over UI, and its keyboard inputs while UI has keyboard focus.
+    pub fn world(mut self) -> Self {
+        self.world = true;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    pub fn mode(&self) -> ContextMode {
+        self.mode
+    }
+
+    pub fn is_world(&self) -> bool {
+        self.world
+    }
+}
+
+/// How a context treats the inputs of the contexts below it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContextMode {
+    /// Lower contexts see every input
+    #[default]
+    PassThrough,
+    /// Inputs bound in this context are hidden from lower contexts
+    Consume,
+    /// Lower contexts see no inputs at all, as for a modal menu
+    Block,
+}
+
+/// The stack of active input contexts
+#[derive(Debug, Default, Resource)]
+pub struct InputContexts {
+    active: Vec<Id>,
+}
+
+impl InputContexts {
+    pub fn new() -> Self {
+        Self { active: Vec::new() }
+    }
+
+    /// Activates a context, placing it above others of the same priority
+    pub fn push(&mut self, id: Id) {
+        self.remove(id);
+        self.active.push(id);
+    }
+
+    pub fn remove(&mut self, id: Id) {
+        self.active.retain(|&a| a != id);
+    }
+
+    pub fn is_active(&self, id: Id) -> bool {
+        self.active.contains(&id)
+    }
+
+    /// Returns the contexts that can receive input, from highest priority to lowest. Contexts
+    /// below one that blocks are left out.
+    pub fn visible<'a>(&self, registry: &'a Registry<InputContext>) -> Vec<(Id, &'a InputContext)> {
+        let mut contexts: Vec<(Id, &InputContext)> = self
+            .active
+            .iter()
+            .rev()
+            .filter_map(|&id| registry.get(id).map(|context| (id, context)))
+            .collect();
+        // Stable, so the most recently pushed stays first within a priority
+        contexts.sort_by_key(|(_, context)| std::cmp::Reverse(context.priority));
+
+        if let Some(block) = contexts
+            .iter()
+            .position(|(_, context)| context.mode == ContextMode::Block)
+        {
+            contexts.truncate(block + 1);
+        }
+        contexts
+    }
+}
+
+pub(super) fn setup_input_contexts(
+    mut contexts: ResMut<InputContexts>,
+    registry: Res<Registry<InputContext>>,
+) {
+    for (&id, context) in registry.iter() {
+        if context.active_by_default {
+            contexts.push(id);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use bevy::{
+    camera::NormalizedRenderTarget, ecs::system::SystemParam, prelude::*, window::PrimaryWindow,
+};
+
+use crate::input::{InputState, touch::Gestures};
+
+/// The windows and cameras the cursor is seen through
+#[derive(SystemParam)]
+pub(super) struct CursorQuery<'w, 's> {
+    windows: Query<'w, 's, (Entity, &'static Window)>,
+    primary: Query<'w, 's, Entity, With<PrimaryWindow>>,
+    cameras: Query<'w, 's, (Entity, &'static Camera, &'static GlobalTransform)>,
+}
+
+impl CursorQuery<'_, '_> {
+    /// Finds the topmost active camera drawing to `window` at `position`
+    fn camera_at(
+        &self,
+        window: Entity,
+        position: Vec2,
+    ) -> Option<(Entity, &Camera, &GlobalTransform)> {
+        let primary = self.primary.single().ok();
+        self.cameras
+            .iter()
+            .filter(|(_, camera, _)| camera.is_active)
+            .filter(|(_, camera, _)| {
+                matches!(
+                    camera.target.normalize(primary),
+                    Some(NormalizedRenderTarget::Window(target)) if target.entity() == window
+                )
+            })
+            .filter(|(_, camera, _)| {
+                camera
+                    .logical_viewport_rect()
+                    .is_some_and(|rect| rect.contains(position))
+            })
+            .max_by_key(|(_, camera, _)| camera.order)
+    }
+}
+
+pub(super) fn update_cursor(
+    state: &mut InputState,
+    cursor: &CursorQuery,
+    motion: Vec2,
+    touch: Option<Vec2>,
+) {
+    let previous = state.mouse;
+    let previous_window = state.cursor_window;
+    let previous_camera = state.cursor_camera;
+
+    // Only one window can hold the cursor at a time. Without a mouse, a single finger on a touch
+    // screen stands in for it.
+    let hovered = cursor
+        .windows
+        .iter()
+        .find_map(|(entity, window)| Some((entity, window.cursor_position()?)))
+        .or_else(|| Some((cursor.primary.single().ok()?, touch?)));
+    state.cursor_window = hovered.map(|(window, _)| window);
+    state.mouse = hovered.map(|(_, position)| position);
+    state.mouse_delta = motion;
+
+    let camera = hovered.and_then(|(window, position)| cursor.camera_at(window, position));
+    state.cursor_camera = camera.map(|(entity, _, _)| entity);
+    let to_world = |position| {
+        let (_, camera, global) = camera?;
+        camera.viewport_to_world_2d(global, position).ok()
+    };
+    state.mouse_world = state.mouse.and_then(to_world);
+
+    // Both ends are measured through the camera as it is now, so moving the camera doesn't count
+    // as moving the cursor. Crossing into another window or viewport doesn't either.
+    let same_view =
+        previous_window == state.cursor_window && previous_camera == state.cursor_camera;
+    state.mouse_world_delta = match (state.mouse, previous) {
+        (Some(current), Some(previous)) if same_view => {
+            match (to_world(current), to_world(previous)) {
+                (Some(a), Some(b)) => a - b,
+                _ => Vec2::ZERO,
+            }
+        }
+        _ => Vec2::ZERO,
+    };
+}
+
+/// Works out how far a gesture pan moved across the world, as for
+/// [`InputState::mouse_world_delta`]
+pub(super) fn update_gesture_pan(
+    state: &mut InputState,
+    cursor: &CursorQuery,
+    gestures: &Gestures,
+) {
+    let window = state.cursor_window.or_else(|| cursor.primary.single().ok());
+
+    let camera = gestures
+        .origin
+        .and_then(|origin| Some((origin, cursor.camera_at(window?, origin)?)));
+
+    // Two fingers on a touch screen leave no cursor, so the gesture decides which camera the
+    // input goes to
+    if state.cursor_camera.is_none() {
+        state.cursor_camera = camera.map(|(_, (entity, _, _))| entity);
+    }
+
+    state.gesture_world_delta = (|| {
+        let (origin, (_, camera, global)) = camera?;
+        let from = camera.viewport_to_world_2d(global, origin).ok()?;
+        let to = camera
+            .viewport_to_world_2d(global, origin + gestures.pan)
+            .ok()?;
+        Some(to - from)
+    })()
+    .unwrap_or(Vec2::ZERO);
+}
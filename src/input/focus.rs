@@ -0,0 +1,61 @@
+use bevy::{
+    ecs::system::SystemParam,
+    input_focus::InputFocus,
+    picking::{hover::HoverMap, pointer::PointerId},
+    prelude::*,
+};
+
+use crate::input::InputType;
+
+/// Reads whether the UI currently has hold of the pointer or the keyboard
+#[derive(SystemParam)]
+pub(super) struct UiFocus<'w, 's> {
+    hover_map: Option<Res<'w, HoverMap>>,
+    nodes: Query<'w, 's, (), With<Node>>,
+    focus: Option<Res<'w, InputFocus>>,
+}
+
+impl UiFocus<'_, '_> {
+    /// Whether the mouse or a finger is over a UI node. UI nodes block picking of whatever is
+    /// beneath them, so any hovered node means the world can't be under the pointer.
+    pub fn pointer_captured(&self) -> bool {
+        self.hover_map.as_ref().is_some_and(|hover_map| {
+            hover_map
+                .iter()
+                .filter(|(pointer, _)| matches!(pointer, PointerId::Mouse | PointerId::Touch(_)))
+                .any(|(_, hovered)| hovered.keys().any(|&entity| self.nodes.contains(entity)))
+        })
+    }
+
+    /// Whether a UI element, such as a text box, has keyboard focus
+    pub fn keyboard_captured(&self) -> bool {
+        self.focus
+            .as_ref()
+            .is_some_and(|focus| focus.get().is_some())
+    }
+
+    pub fn capture(&self) -> UiCapture {
+        UiCapture {
+            pointer: self.pointer_captured(),
+            keyboard: self.keyboard_captured(),
+        }
+    }
+}
+
+/// Which devices the UI has taken from the world this frame
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct UiCapture {
+    pub pointer: bool,
+    pub keyboard: bool,
+}
+
+impl UiCapture {
+    /// Whether the input is on a device the UI has taken
+    pub fn blocks(&self, input_type: InputType) -> bool {
+        match input_type {
+            InputType::KeyButton(_) => self.keyboard,
+            InputType::MouseButton(_) | InputType::Touch(_) => self.pointer,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,1151 @@
+use std::{
+    collections::{HashMap, HashSet, hash_map},
+    fmt,
+};
+
+use bevy::{
+    ecs::system::SystemParam,
+    input::{
+        InputSystems,
+        mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseWheel},
+        touch::Touches,
+    },
+    picking::PickingSystems,
+    prelude::*,
+    time::TimeSystems,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input::{
+        analog::{AnalogSettings, AxisDirection},
+        axis::{AxisMap, AxisMapping, update_axes},
+        config::{INPUT_CONFIG_PATH, InputConfig},
+        context::{ContextMode, InputContext, InputContexts, setup_input_contexts},
+        cursor::{CursorQuery, update_cursor, update_gesture_pan},
+        focus::{UiCapture, UiFocus},
+        record::{
+            InputPlayback, check_replay, record_input, recording, replay_input, replay_time,
+            replaying, save_recording,
+        },
+        touch::{Gestures, TouchInput},
+        trigger::{Timing, Trigger, update_triggers},
+    },
+    modding::{
+        PostModLoad,
+        registry::{Id, Registry},
+    },
+};
+
+pub mod analog;
+pub mod axis;
+pub mod config;
+pub mod context;
+pub mod cursor;
+pub mod focus;
+pub mod record;
+pub mod touch;
+pub mod trigger;
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputState>()
+            .init_resource::<InputMap>()
+            .init_resource::<Registry<InputMapping>>()
+            .init_resource::<AxisMap>()
+            .init_resource::<Registry<AxisMapping>>()
+            .init_resource::<InputContexts>()
+            .init_resource::<Registry<InputContext>>()
+            .init_resource::<InputConfig>()
+            .init_resource::<AnalogSettings>()
+            .init_resource::<InputPlayback>()
+            .add_systems(First, replay_time.run_if(replaying).before(TimeSystems))
+            .add_systems(
+                PreUpdate,
+                (
+                    input_state_system.run_if(not(replaying)),
+                    record_input.run_if(recording),
+                    replay_input.run_if(replaying),
+                )
+                    .chain()
+                    .after(InputSystems)
+                    .after(PickingSystems::Hover),
+            )
+            .add_systems(
+                PostModLoad,
+                (setup_input_map, setup_input_contexts, check_replay),
+            )
+            .add_systems(
+                Last,
+                (
+                    save_input_map.run_if(
+                        resource_changed::<InputMap>
+                            .or(resource_changed::<AxisMap>)
+                            .or(resource_changed::<AnalogSettings>)
+                            .and(not(resource_added::<InputMap>)),
+                    ),
+                    save_recording.run_if(recording),
+                ),
+            );
+    }
+}
+
+/// The input maps, along with the registries and contexts that decide which mappings are live
+#[derive(SystemParam)]
+struct Mappings<'w> {
+    map: Res<'w, InputMap>,
+    axis_map: Res<'w, AxisMap>,
+    registry: Res<'w, Registry<InputMapping>>,
+    axis_registry: Res<'w, Registry<AxisMapping>>,
+    contexts: Res<'w, InputContexts>,
+    context_registry: Res<'w, Registry<InputContext>>,
+}
+
+fn input_state_system(
+    state: ResMut<InputState>,
+    mappings: Mappings,
+    key_buttons: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    analog: Res<AnalogSettings>,
+    mouse_scroll: Res<AccumulatedMouseScroll>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    scroll: MessageReader<MouseWheel>,
+    cursor: CursorQuery,
+    mut touch: TouchInput,
+    ui: UiFocus,
+    time: Res<Time<Real>>,
+) {
+    let state = state.into_inner();
+    state.capture = ui.capture();
+
+    update_cursor(state, &cursor, mouse_motion.delta, touch.single_touch());
+    let gestures = touch.read(state.mouse);
+    update_gesture_pan(state, &cursor, &gestures);
+
+    let raw = RawInputs {
+        key_buttons: key_buttons.into_inner(),
+        mouse_buttons: mouse_buttons.into_inner(),
+        gamepads: gamepads.iter().collect(),
+        touches: touch.touches(),
+        scroll: analog.scroll_lines(mouse_scroll.unit, mouse_scroll.delta),
+        analog: analog.into_inner(),
+        mouse_motion: mouse_motion.delta,
+        gestures,
+    };
+    update_buttons(state, &mappings, &raw);
+    update_triggers(
+        state,
+        mappings.map.map.keys().copied(),
+        &mappings.registry,
+        time.elapsed_secs(),
+    );
+
+    let visible: HashMap<Id, &InputContext> = mappings
+        .contexts
+        .visible(&mappings.context_registry)
+        .into_iter()
+        .collect();
+    update_axes(state, &mappings.axis_map, &raw, |id| {
+        match mappings.axis_registry.get(id)?.context() {
+            Some(context) => visible.get(&context).map(|info| info.is_world()),
+            None => Some(false),
+        }
+    });
+    update_scroll(state, scroll, raw.analog);
+}
+
+fn update_buttons(state: &mut InputState, mappings: &Mappings, raw: &RawInputs) {
+    state.clear();
+
+    let live = live_bindings(mappings);
+
+    // When several bindings share a physical input, only the chords with the most modifiers held
+    // are active, so Shift + Equal doesn't also trigger Equal
+    let mut specificity: HashMap<InputType, usize> = HashMap::new();
+    for &input in live.values().flat_map(|live| &live.bindings) {
+        if input.modifiers_match(raw.key_buttons) {
+            let best = specificity.entry(input.input_type).or_default();
+            *best = (*best).max(input.modifiers.len());
+        }
+    }
+    let active = |input: &&Input| {
+        specificity
+            .get(&input.input_type)
+            .is_some_and(|&best| input.modifiers.len() == best)
+    };
+
+    for &id in mappings.map.map.keys() {
+        // Mappings in inactive contexts have no live bindings, so they are released
+        let (bindings, world) = live
+            .get(&id)
+            .map(|live| (live.bindings.as_slice(), live.world))
+            .unwrap_or_default();
+
+        // World actions can't be started from a device the UI has taken, but one that is already
+        // held carries on, so dragging the camera over UI doesn't drop it
+        let was_pressed = state.pressed(id);
+        let capture = state.capture;
+        let usable = |input: &&Input| {
+            active(input) && (was_pressed || !world || !capture.blocks(input.input_type))
+        };
+
+        // The strongest binding decides the analog value
+        let value = bindings
+            .iter()
+            .copied()
+            .filter(usable)
+            .map(|input| input.value(raw))
+            .fold(0.0, f32::max);
+        if value > 0.0 {
+            state.values.insert(id, value);
+        }
+
+        // The action stays pressed while any of its bindings are held. Releases are checked
+        // whatever order the keys were let go in, so releasing a modifier first also releases
+        // the action.
+        let held = bindings
+            .iter()
+            .copied()
+            .filter(usable)
+            .any(|input| input.pressed(raw));
+
+        if !held {
+            if state.pressed(id) {
+                state.release(id);
+            }
+            continue;
+        }
+
+        if !was_pressed
+            && bindings
+                .iter()
+                .copied()
+                .filter(active)
+                .any(|input| input.just_pressed(raw))
+        {
+            state.press(id);
+        }
+    }
+}
+
+/// The bindings of a mapping that can currently fire
+struct LiveBindings<'a> {
+    bindings: Vec<&'a Input>,
+    /// Whether the mapping's context acts on the world
+    world: bool,
+}
+
+/// Collects the bindings of each mapping that can currently fire. Contexts are walked from highest
+/// priority down, so a consuming context claims its inputs before lower contexts see them.
+fn live_bindings<'a>(mappings: &'a Mappings) -> HashMap<Id, LiveBindings<'a>> {
+    let mut live = HashMap::new();
+    let mut claimed: HashSet<InputType> = HashSet::new();
+
+    for (context, info) in mappings.contexts.visible(&mappings.context_registry) {
+        let mut bound = Vec::new();
+        for (&id, bindings) in mappings.map.iter() {
+            if mappings.registry.get(id).and_then(InputMapping::context) != Some(context) {
+                continue;
+            }
+
+            let unclaimed = bindings
+                .iter()
+                .filter(|input| !claimed.contains(&input.input_type))
+                .collect();
+            live.insert(
+                id,
+                LiveBindings {
+                    bindings: unclaimed,
+                    world: info.is_world(),
+                },
+            );
+            bound.extend(bindings.iter().map(|input| input.input_type));
+        }
+
+        if info.mode() == ContextMode::Consume {
+            claimed.extend(bound);
+        }
+    }
+
+    // Mappings outside of any context are always live
+    for (&id, bindings) in mappings.map.iter() {
+        if mappings
+            .registry
+            .get(id)
+            .is_some_and(|mapping| mapping.context().is_none())
+        {
+            live.insert(
+                id,
+                LiveBindings {
+                    bindings: bindings.iter().collect(),
+                    world: false,
+                },
+            );
+        }
+    }
+
+    live
+}
+
+fn update_scroll(
+    state: &mut InputState,
+    mut scroll: MessageReader<MouseWheel>,
+    analog: &AnalogSettings,
+) {
+    state.scroll = scroll.read().fold(0.0, |sum, event| {
+        sum + analog
+            .scroll_lines(event.unit, Vec2::new(event.x, event.y))
+            .y
+    });
+}
+
+fn setup_input_map(
+    mut map: ResMut<InputMap>,
+    mut axis_map: ResMut<AxisMap>,
+    mut config: ResMut<InputConfig>,
+    mut analog: ResMut<AnalogSettings>,
+    registry: Res<Registry<InputMapping>>,
+    axis_registry: Res<Registry<AxisMapping>>,
+) {
+    for (&id, input) in registry.iter() {
+        map.insert(id, input.default.clone());
+    }
+    for (&id, axis) in axis_registry.iter() {
+        axis_map.insert(id, axis.defaults().to_vec());
+    }
+
+    // User bindings override the defaults
+    *config = InputConfig::load(INPUT_CONFIG_PATH);
+    config.apply(&mut map, &registry);
+    config.apply_axes(&mut axis_map, &axis_registry);
+    *analog = config.analog().clone();
+}
+
+fn save_input_map(
+    map: Res<InputMap>,
+    axis_map: Res<AxisMap>,
+    analog: Res<AnalogSettings>,
+    mut config: ResMut<InputConfig>,
+    registry: Res<Registry<InputMapping>>,
+    axis_registry: Res<Registry<AxisMapping>>,
+) {
+    config.update(&map, &registry);
+    config.update_axes(&axis_map, &axis_registry);
+    config.set_analog(analog.clone());
+    config.save(INPUT_CONFIG_PATH);
+}
+
+/// The state of the physical input devices that bindings are read from
+pub struct RawInputs<'a> {
+    pub key_buttons: &'a ButtonInput<KeyCode>,
+    pub mouse_buttons: &'a ButtonInput<MouseButton>,
+    pub gamepads: Vec<&'a Gamepad>,
+    pub touches: &'a Touches,
+    pub analog: &'a AnalogSettings,
+    /// The distance scrolled this frame, in lines
+    pub scroll: Vec2,
+    /// The distance the mouse moved this frame
+    pub mouse_motion: Vec2,
+    pub gestures: Gestures,
+}
+
+/// Contains the states for each input mapping
+#[derive(Debug, Default, Resource)]
+pub struct InputState {
+    pressed: HashSet<Id>,
+    just_pressed: HashSet<Id>,
+    just_released: HashSet<Id>,
+    values: HashMap<Id, f32>,
+    axes: HashMap<Id, f32>,
+    /// The cursor position in the window it is over
+    mouse: Option<Vec2>,
+    cursor_window: Option<Entity>,
+    /// The camera whose viewport the cursor is over
+    cursor_camera: Option<Entity>,
+    mouse_world: Option<Vec2>,
+    mouse_delta: Vec2,
+    mouse_world_delta: Vec2,
+    gesture_world_delta: Vec2,
+    scroll: f32,
+    capture: UiCapture,
+    triggered: HashSet<Id>,
+    timings: HashMap<Id, Timing>,
+    /// The real time in seconds at the last update
+    time: f32,
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        Self {
+            pressed: HashSet::new(),
+            just_pressed: HashSet::new(),
+            just_released: HashSet::new(),
+            values: HashMap::new(),
+            axes: HashMap::new(),
+            mouse: None,
+            cursor_window: None,
+            cursor_camera: None,
+            mouse_world: None,
+            mouse_delta: Vec2::ZERO,
+            mouse_world_delta: Vec2::ZERO,
+            gesture_world_delta: Vec2::ZERO,
+            scroll: 0.0,
+            capture: UiCapture::default(),
+            triggered: HashSet::new(),
+            timings: HashMap::new(),
+            time: 0.0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+        self.values.clear();
+        self.axes.clear();
+    }
+
+    pub fn press(&mut self, id: Id) {
+        self.pressed.insert(id);
+        self.just_pressed.insert(id);
+    }
+
+    pub fn release(&mut self, id: Id) {
+        self.pressed.remove(&id);
+        self.just_released.insert(id);
+    }
+
+    pub fn pressed(&self, id: Id) -> bool {
+        self.pressed.contains(&id)
+    }
+
+    pub fn just_pressed(&self, id: Id) -> bool {
+        self.just_pressed.contains(&id)
+    }
+
+    pub fn just_released(&self, id: Id) -> bool {
+        self.just_released.contains(&id)
+    }
+
+    /// Whether the mapping's [`Trigger`] fired this frame
+    pub fn triggered(&self, id: Id) -> bool {
+        self.triggered.contains(&id)
+    }
+
+    /// Returns how many seconds the action has been held, or zero if it isn't
+    pub fn held_for(&self, id: Id) -> f32 {
+        match self.timings.get(&id) {
+            Some(timing) if self.pressed(id) => self.time - timing.pressed_at(),
+            _ => 0.0,
+        }
+    }
+
+    pub fn axis(&self, positive: Id, negative: Id) -> f32 {
+        let positive = self.pressed.contains(&positive) as i8;
+        let negative = self.pressed.contains(&negative) as i8;
+        (positive - negative) as f32
+    }
+
+    pub fn vec2(&self, positive_x: Id, negative_x: Id, positive_y: Id, negative_y: Id) -> Vec2 {
+        let x = self.axis(positive_x, negative_x);
+        let y = self.axis(positive_y, negative_y);
+        Vec2::new(x, y)
+    }
+
+    /// Returns how strongly an action is held, from zero to one. Digital bindings are either zero
+    /// or one, while analog bindings such as sticks and triggers give values in between.
+    pub fn value(&self, id: Id) -> f32 {
+        self.values.get(&id).copied().unwrap_or(0.0)
+    }
+
+    pub fn analog_axis(&self, positive: Id, negative: Id) -> f32 {
+        self.value(positive) - self.value(negative)
+    }
+
+    pub fn analog_vec2(
+        &self,
+        positive_x: Id,
+        negative_x: Id,
+        positive_y: Id,
+        negative_y: Id,
+    ) -> Vec2 {
+        let x = self.analog_axis(positive_x, negative_x);
+        let y = self.analog_axis(positive_y, negative_y);
+        Vec2::new(x, y)
+    }
+
+    pub fn set_axis(&mut self, id: Id, value: f32) {
+        self.axes.insert(id, value);
+    }
+
+    /// Returns the value of an axis mapping, registered in [`Registry<AxisMapping>`]
+    pub fn axis_value(&self, id: Id) -> f32 {
+        self.axes.get(&id).copied().unwrap_or(0.0)
+    }
+
+    /// Returns the cursor position in logical pixels from the top left of the window it is over
+    pub fn mouse(&self) -> Option<Vec2> {
+        self.mouse
+    }
+
+    /// Returns the window the cursor is over
+    pub fn cursor_window(&self) -> Option<Entity> {
+        self.cursor_window
+    }
+
+    /// Returns the topmost camera whose viewport the cursor is over, which pointer input is routed
+    /// to
+    pub fn cursor_camera(&self) -> Option<Entity> {
+        self.cursor_camera
+    }
+
+    pub fn cursor_in_window(&self) -> bool {
+        self.mouse.is_some()
+    }
+
+    /// Returns the cursor position in the world, as seen by the topmost camera under the cursor
+    pub fn mouse_world(&self) -> Option<Vec2> {
+        self.mouse_world
+    }
+
+    /// Returns how far the mouse moved this frame. This is read from the device, so it still
+    /// changes when the cursor is locked or at the edge of the screen.
+    pub fn mouse_delta(&self) -> Vec2 {
+        self.mouse_delta
+    }
+
+    /// Returns how far the cursor moved across the world this frame, ignoring any movement of the
+    /// camera itself
+    pub fn mouse_world_delta(&self) -> Vec2 {
+        self.mouse_world_delta
+    }
+
+    /// Returns how far a two finger or trackpad pan moved across the world this frame
+    pub fn gesture_world_delta(&self) -> Vec2 {
+        self.gesture_world_delta
+    }
+
+    pub fn scroll(&self) -> f32 {
+        self.scroll
+    }
+
+    /// Whether the pointer is over UI, so world actions ignore the mouse
+    pub fn pointer_captured(&self) -> bool {
+        self.capture.pointer
+    }
+
+    /// Whether UI has keyboard focus, so world actions ignore the keyboard
+    pub fn keyboard_captured(&self) -> bool {
+        self.capture.keyboard
+    }
+}
+
+/// Contains the mappings of ids to physical inputs.
+///
+/// Each id can have several alternative bindings, the first two being its primary and secondary
+/// bindings.
+#[derive(Debug, Default, Resource)]
+pub struct InputMap {
+    map: HashMap<Id, Vec<Input>>,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, id: Id) -> Option<&[Input]> {
+        self.map.get(&id).map(Vec::as_slice)
+    }
+
+    pub fn primary(&self, id: Id) -> Option<&Input> {
+        self.get_slot(id, 0)
+    }
+
+    pub fn secondary(&self, id: Id) -> Option<&Input> {
+        self.get_slot(id, 1)
+    }
+
+    pub fn get_slot(&self, id: Id, slot: usize) -> Option<&Input> {
+        self.map.get(&id).and_then(|bindings| bindings.get(slot))
+    }
+
+    pub fn insert(&mut self, id: Id, bindings: Vec<Input>) {
+        self.map.insert(id, bindings);
+    }
+
+    /// Binds `input` to the given slot of `id`, leaving any earlier empty slots unbound
+    pub fn set_slot(&mut self, id: Id, slot: usize, input: Input) {
+        let bindings = self.map.entry(id).or_default();
+        if slot >= bindings.len() {
+            bindings.resize(slot + 1, Input::none());
+        }
+        bindings[slot] = input;
+
+        // Trailing unbound slots carry no information
+        while bindings.last().is_some_and(Input::is_none) {
+            bindings.pop();
+        }
+    }
+
+    /// Adds `input` as an alternative binding for `id`
+    pub fn add(&mut self, id: Id, input: Input) {
+        self.map.entry(id).or_default().push(input);
+    }
+
+    pub fn iter<'a>(&'a self) -> hash_map::Iter<'a, Id, Vec<Input>> {
+        self.map.iter()
+    }
+
+    /// Returns the bindings, as id and slot, that are shared with another id in the same context.
+    /// Mappings with different triggers may share a binding, as with a tap and a long press on the
+    /// same key.
+    pub fn conflicts(&self, registry: &Registry<InputMapping>) -> HashSet<(Id, usize)> {
+        let context = |id| registry.get(id).and_then(InputMapping::context);
+        let trigger = |id| registry.get(id).map(InputMapping::trigger);
+
+        let mut conflicts = HashSet::new();
+        for (&a, bindings_a) in self.map.iter() {
+            for (slot, input_a) in bindings_a.iter().enumerate() {
+                if input_a.is_none() {
+                    continue;
+                }
+
+                let conflicting = self.map.iter().any(|(&b, bindings_b)| {
+                    a != b
+                        && context(a) == context(b)
+                        && trigger(a) == trigger(b)
+                        && bindings_b.contains(input_a)
+                });
+                if conflicting {
+                    conflicts.insert((a, slot));
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Input {
+    #[serde(flatten, with = "unbound_as_absent")]
+    input_type: InputType,
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    modifiers: HashSet<KeyCode>,
+    #[serde(
+        rename = "match",
+        default,
+        skip_serializing_if = "ModifierMatch::is_superset"
+    )]
+    modifier_match: ModifierMatch,
+}
+
+impl Input {
+    pub fn new(input_type: InputType, modifiers: HashSet<KeyCode>) -> Self {
+        Self {
+            input_type,
+            modifiers,
+            modifier_match: ModifierMatch::Superset,
+        }
+    }
+
+    pub fn from_input_type(input_type: InputType) -> Self {
+        Self {
+            input_type,
+            modifiers: HashSet::new(),
+            modifier_match: ModifierMatch::Superset,
+        }
+    }
+
+    pub fn none() -> Self {
+        Self {
+            input_type: InputType::None,
+            modifiers: HashSet::new(),
+            modifier_match: ModifierMatch::Superset,
+        }
+    }
+
+    pub fn key(key_code: KeyCode) -> Self {
+        Self {
+            input_type: InputType::KeyButton(key_code),
+            modifiers: HashSet::new(),
+            modifier_match: ModifierMatch::Superset,
+        }
+    }
+
+    pub fn mouse(mouse_button: MouseButton) -> Self {
+        Self {
+            input_type: InputType::MouseButton(mouse_button),
+            modifiers: HashSet::new(),
+            modifier_match: ModifierMatch::Superset,
+        }
+    }
+
+    pub fn gamepad(gamepad_button: GamepadButton) -> Self {
+        Self {
+            input_type: InputType::GamepadButton(gamepad_button),
+            modifiers: HashSet::new(),
+            modifier_match: ModifierMatch::Superset,
+        }
+    }
+
+    /// Binds touching the screen with exactly `fingers` fingers
+    pub fn touch(fingers: u8) -> Self {
+        Self {
+            input_type: InputType::Touch(fingers),
+            modifiers: HashSet::new(),
+            modifier_match: ModifierMatch::Superset,
+        }
+    }
+
+    pub fn axis(gamepad_axis: GamepadAxis, direction: AxisDirection) -> Self {
+        Self {
+            input_type: InputType::GamepadAxis(gamepad_axis, direction),
+            modifiers: HashSet::new(),
+            modifier_match: ModifierMatch::Superset,
+        }
+    }
+
+    pub fn with_modifier(mut self, modifier: KeyCode) -> Self {
+        self.modifiers.insert(modifier);
+        self
+    }
+
+    pub fn with_lshift(self) -> Self {
+        self.with_modifier(KeyCode::ShiftLeft)
+    }
+
+    pub fn with_rshift(self) -> Self {
+        self.with_modifier(KeyCode::ShiftRight)
+    }
+
+    pub fn with_lctrl(self) -> Self {
+        self.with_modifier(KeyCode::ControlLeft)
+    }
+
+    pub fn with_rctrl(self) -> Self {
+        self.with_modifier(KeyCode::ControlRight)
+    }
+
+    pub fn with_lalt(self) -> Self {
+        self.with_modifier(KeyCode::AltLeft)
+    }
+
+    pub fn with_ralt(self) -> Self {
+        self.with_modifier(KeyCode::AltRight)
+    }
+
+    /// Only matches when no modifiers other than the binding's own are held
+    pub fn exact(mut self) -> Self {
+        self.modifier_match = ModifierMatch::Exact;
+        self
+    }
+
+    pub fn input_type(&self) -> InputType {
+        self.input_type
+    }
+
+    pub fn modifiers(&self) -> &HashSet<KeyCode> {
+        &self.modifiers
+    }
+
+    pub fn modifier_match(&self) -> ModifierMatch {
+        self.modifier_match
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.input_type == InputType::None
+    }
+
+    pub fn pressed(&self, raw: &RawInputs) -> bool {
+        self.modifiers_match(raw.key_buttons) && self.input_type.pressed(raw)
+    }
+
+    pub fn just_pressed(&self, raw: &RawInputs) -> bool {
+        self.modifiers_match(raw.key_buttons) && self.input_type.just_pressed(raw)
+    }
+
+    pub fn value(&self, raw: &RawInputs) -> f32 {
+        if !self.modifiers_match(raw.key_buttons) {
+            return 0.0;
+        }
+        self.input_type.value(raw)
+    }
+
+    /// Checks whether the held modifiers satisfy the binding
+    pub fn modifiers_match(&self, key_buttons: &ButtonInput<KeyCode>) -> bool {
+        if !self.modifiers.iter().all(|&m| key_buttons.pressed(m)) {
+            return false;
+        }
+
+        match self.modifier_match {
+            ModifierMatch::Superset => true,
+            ModifierMatch::Exact => key_buttons.get_pressed().all(|&k| {
+                // A modifier bound as the main key doesn't count as an extra modifier
+                !is_modifier(k)
+                    || self.modifiers.contains(&k)
+                    || self.input_type == InputType::KeyButton(k)
+            }),
+        }
+    }
+}
+
+/// How the modifiers held by the player are compared with those of a binding
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModifierMatch {
+    /// The binding's modifiers must be held, but others may be too
+    #[default]
+    Superset,
+    /// Exactly the binding's modifiers must be held
+    Exact,
+}
+
+impl ModifierMatch {
+    pub fn is_superset(&self) -> bool {
+        *self == ModifierMatch::Superset
+    }
+}
+
+/// Whether the key is a modifier, which can be held to form chords with other inputs
+pub fn is_modifier(key: KeyCode) -> bool {
+    matches!(
+        key,
+        KeyCode::ShiftLeft
+            | KeyCode::ShiftRight
+            | KeyCode::ControlLeft
+            | KeyCode::ControlRight
+            | KeyCode::AltLeft
+            | KeyCode::AltRight
+            | KeyCode::SuperLeft
+            | KeyCode::SuperRight
+    )
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Sort the modifiers so the same binding always displays the same way
+        let mut modifiers: Vec<String> = self.modifiers.iter().map(|m| format!("{m:?}")).collect();
+        modifiers.sort();
+
+        for modifier in modifiers {
+            write!(f, "{modifier} + ")?;
+        }
+        write!(f, "{}", self.input_type)
+    }
+}
+
+impl From<InputType> for Input {
+    fn from(value: InputType) -> Self {
+        Self::from_input_type(value)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputType {
+    #[default]
+    #[serde(skip)]
+    None,
+    #[serde(rename = "key")]
+    KeyButton(KeyCode),
+    #[serde(rename = "mouse")]
+    MouseButton(MouseButton),
+    #[serde(rename = "gamepad")]
+    GamepadButton(GamepadButton),
+    /// One half of a gamepad axis, such as pushing the left stick up
+    #[serde(rename = "axis")]
+    GamepadAxis(GamepadAxis, AxisDirection),
+    /// Touching the screen with this many fingers
+    #[serde(rename = "touch")]
+    Touch(u8),
+}
+
+impl InputType {
+    pub fn pressed(&self, raw: &RawInputs) -> bool {
+        match *self {
+            InputType::None => false,
+            InputType::KeyButton(key_code) => raw.key_buttons.pressed(key_code),
+            InputType::MouseButton(mouse_button) => raw.mouse_buttons.pressed(mouse_button),
+            InputType::GamepadButton(gamepad_button) => {
+                raw.gamepads.iter().any(|g| g.pressed(gamepad_button))
+            }
+            InputType::GamepadAxis(..) => self.value(raw) >= raw.analog.press_threshold,
+            InputType::Touch(fingers) => raw.touches.iter().count() == fingers as usize,
+        }
+    }
+
+    pub fn just_pressed(&self, raw: &RawInputs) -> bool {
+        match *self {
+            InputType::None => false,
+            InputType::KeyButton(key_code) => raw.key_buttons.just_pressed(key_code),
+            InputType::MouseButton(mouse_button) => raw.mouse_buttons.just_pressed(mouse_button),
+            InputType::GamepadButton(gamepad_button) => {
+                raw.gamepads.iter().any(|g| g.just_pressed(gamepad_button))
+            }
+            // Axes have no previous state, but actions are only pressed if they weren't already
+            InputType::GamepadAxis(..) => self.pressed(raw),
+            // Only adding a finger counts, as lifting one to end a pinch shouldn't press what is
+            // under the finger left behind
+            InputType::Touch(_) => {
+                self.pressed(raw)
+                    && raw.touches.any_just_pressed()
+                    && !raw.touches.any_just_released()
+                    && !raw.touches.any_just_canceled()
+            }
+        }
+    }
+
+    /// Returns how far the input is held, from zero to one
+    pub fn value(&self, raw: &RawInputs) -> f32 {
+        match *self {
+            InputType::None
+            | InputType::KeyButton(_)
+            | InputType::MouseButton(_)
+            | InputType::Touch(_) => {
+                if self.pressed(raw) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            InputType::GamepadButton(gamepad_button) => raw
+                .gamepads
+                .iter()
+                .map(|g| {
+                    let fallback = if g.pressed(gamepad_button) { 1.0 } else { 0.0 };
+                    raw.analog
+                        .apply_deadzone(g.get(gamepad_button).unwrap_or(fallback))
+                })
+                .fold(0.0, f32::max),
+            InputType::GamepadAxis(gamepad_axis, direction) => raw
+                .gamepads
+                .iter()
+                .map(|g| {
+                    let value = g.get(gamepad_axis).unwrap_or(0.0) * direction.sign();
+                    raw.analog.apply_deadzone(value.max(0.0))
+                })
+                .fold(0.0, f32::max),
+        }
+    }
+}
+
+impl fmt::Display for InputType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputType::None => write!(f, "Unbound"),
+            InputType::KeyButton(key_code) => write!(f, "{key_code:?}"),
+            InputType::MouseButton(mouse_button) => write!(f, "Mouse {mouse_button:?}"),
+            InputType::GamepadButton(gamepad_button) => write!(f, "Gamepad {gamepad_button:?}"),
+            InputType::GamepadAxis(gamepad_axis, direction) => {
+                let sign = match direction {
+                    AxisDirection::Positive => '+',
+                    AxisDirection::Negative => '-',
+                };
+                write!(f, "Gamepad {gamepad_axis:?}{sign}")
+            }
+            InputType::Touch(1) => write!(f, "Touch"),
+            InputType::Touch(fingers) => write!(f, "{fingers} Finger Touch"),
+        }
+    }
+}
+
+/// Serialises [`InputType::None`] as the absence of a binding, so unbound inputs are written as an
+/// empty table rather than failing to flatten a unit variant.
+mod unbound_as_absent {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::InputType;
+
+    pub fn serialize<S: Serializer>(
+        input_type: &InputType,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match input_type {
+            InputType::None => serializer.serialize_none(),
+            input_type => serializer.serialize_some(input_type),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<InputType, D::Error> {
+        Option::<InputType>::deserialize(deserializer).map(Option::unwrap_or_default)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct InputMapping {
+    name: String,
+    default: Vec<Input>,
+    context: Option<Id>,
+    trigger: Trigger,
+}
+
+impl InputMapping {
+    pub fn new(name: &str, default: Input) -> Self {
+        Self {
+            name: name.to_string(),
+            default: vec![default],
+            context: None,
+            trigger: Trigger::Press,
+        }
+    }
+
+    /// Places the mapping in an [`InputContext`], so it only fires while that context is active.
+    /// Mappings without a context are always active.
+    pub fn in_context(mut self, context: Id) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Adds an alternative default binding
+    pub fn with_alternative(mut self, input: Input) -> Self {
+        self.default.push(input);
+        self
+    }
+
+    /// Sets when the mapping counts as [triggered](InputState::triggered)
+    pub fn with_trigger(mut self, trigger: Trigger) -> Self {
+        self.trigger = trigger;
+        self
+    }
+
+    pub(crate) fn nameless(default: Input) -> Self {
+        Self::new("", default)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn defaults(&self) -> &[Input] {
+        &self.default
+    }
+
+    pub fn context(&self) -> Option<Id> {
+        self.context
+    }
+
+    pub fn trigger(&self) -> Trigger {
+        self.trigger
+    }
+}
+
+impl From<Input> for InputMapping {
+    fn from(value: Input) -> Self {
+        Self::nameless(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    /// Binds each of `bindings` to its own action, holds down `keys`, and returns which of the
+    /// actions were pressed
+    fn pressed_with(bindings: &[Input], keys: &[KeyCode]) -> Vec<bool> {
+        let mut registry = Registry::<InputMapping>::new();
+        let mut map = InputMap::new();
+        let ids: Vec<Id> = bindings
+            .iter()
+            .enumerate()
+            .map(|(i, binding)| {
+                let id = registry
+                    .register(&format!("test::input::action_{i}"), binding.clone().into())
+                    .unwrap();
+                map.insert(id, vec![binding.clone()]);
+                id
+            })
+            .collect();
+
+        let mut key_buttons = ButtonInput::<KeyCode>::default();
+        for &key in keys {
+            key_buttons.press(key);
+        }
+
+        let mut world = World::new();
+        world.insert_resource(map);
+        world.insert_resource(registry);
+        world.insert_resource(key_buttons);
+        world.insert_resource(InputState::new());
+        world.init_resource::<AxisMap>();
+        world.insert_resource(Registry::<AxisMapping>::new());
+        world.init_resource::<InputContexts>();
+        world.insert_resource(Registry::<InputContext>::new());
+
+        world
+            .run_system_once(
+                |mut state: ResMut<InputState>,
+                 mappings: Mappings,
+                 key_buttons: Res<ButtonInput<KeyCode>>| {
+                    let raw = RawInputs {
+                        key_buttons: &key_buttons,
+                        mouse_buttons: &ButtonInput::default(),
+                        gamepads: Vec::new(),
+                        touches: &Touches::default(),
+                        analog: &AnalogSettings::default(),
+                        scroll: Vec2::ZERO,
+                        mouse_motion: Vec2::ZERO,
+                        gestures: Gestures::default(),
+                    };
+                    update_buttons(&mut state, &mappings, &raw);
+                },
+            )
+            .unwrap();
+
+        let state = world.resource::<InputState>();
+        ids.iter().map(|&id| state.just_pressed(id)).collect()
+    }
+
+    /// Ctrl + 1 saves a bookmark without also recalling it, while 1 alone only recalls it
+    #[test]
+    fn most_specific_chord_wins() {
+        let bindings = [
+            Input::key(KeyCode::Digit1),
+            Input::key(KeyCode::Digit1).with_lctrl(),
+        ];
+        assert_eq!(
+            pressed_with(&bindings, &[KeyCode::ControlLeft, KeyCode::Digit1]),
+            [false, true]
+        );
+        assert_eq!(pressed_with(&bindings, &[KeyCode::Digit1]), [true, false]);
+    }
+
+    /// An exact binding for V only fires with no modifiers held, leaving Ctrl + V to paste
+    #[test]
+    fn exact_binding_ignores_chords() {
+        let bindings = [
+            Input::key(KeyCode::KeyV).exact(),
+            Input::key(KeyCode::KeyV).with_lctrl(),
+        ];
+        assert_eq!(
+            pressed_with(&bindings, &[KeyCode::ControlLeft, KeyCode::KeyV]),
+            [false, true]
+        );
+        assert_eq!(pressed_with(&bindings, &[KeyCode::KeyV]), [true, false]);
+    }
+
+    /// Ctrl + Shift + Z redoes without also undoing
+    #[test]
+    fn extra_modifier_picks_the_longer_chord() {
+        let bindings = [
+            Input::key(KeyCode::KeyZ).with_lctrl(),
+            Input::key(KeyCode::KeyZ).with_lctrl().with_lshift(),
+        ];
+        assert_eq!(
+            pressed_with(
+                &bindings,
+                &[KeyCode::ControlLeft, KeyCode::ShiftLeft, KeyCode::KeyZ]
+            ),
+            [false, true]
+        );
+        assert_eq!(
+            pressed_with(&bindings, &[KeyCode::ControlLeft, KeyCode::KeyZ]),
+            [true, false]
+        );
+    }
+}
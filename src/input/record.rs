@@ -0,0 +1,446 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input::{InputMapping, InputState, axis::AxisMapping, trigger::update_triggers},
+    modding::registry::Registry,
+};
+
+/// Whether input comes from the player, is being recorded, or is replayed from a recording
+#[derive(Debug, Default, Resource)]
+pub enum InputPlayback {
+    #[default]
+    Live,
+    /// Live input that is also saved to `path` when the app exits
+    Recording {
+        path: PathBuf,
+        recording: InputRecording,
+    },
+    /// Input read from a recording rather than the devices, one frame per update
+    Replaying {
+        recording: InputRecording,
+        frame: usize,
+    },
+}
+
+impl InputPlayback {
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self::Recording {
+            path: path.into(),
+            recording: InputRecording::new(),
+        }
+    }
+
+    pub fn replay(recording: InputRecording) -> Self {
+        Self::Replaying {
+            recording,
+            frame: 0,
+        }
+    }
+
+    /// Reads `--record-input <path>` or `--replay-input <path>` from the command line
+    pub fn from_args() -> Self {
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match (arg.as_str(), args.next()) {
+                ("--record-input", Some(path)) => return Self::record(path),
+                ("--replay-input", Some(path)) => {
+                    return Self::replay(InputRecording::load(path));
+                }
+                _ => {}
+            }
+        }
+        Self::Live
+    }
+
+    pub fn is_recording(&self) -> bool {
+        matches!(self, Self::Recording { .. })
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        matches!(self, Self::Replaying { .. })
+    }
+}
+
+pub(super) fn recording(playback: Res<InputPlayback>) -> bool {
+    playback.is_recording()
+}
+
+pub(super) fn replaying(playback: Res<InputPlayback>) -> bool {
+    playback.is_replaying()
+}
+
+/// A sequence of [`InputState`] frames, keyed by registry path so it survives changes to mod load
+/// order
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputRecording {
+    #[serde(default)]
+    frames: Vec<RecordedFrame>,
+}
+
+impl InputRecording {
+    pub fn new() -> Self {
+        Self { frames: Vec::new() }
+    }
+
+    /// Loads the recording at `path`, falling back to an empty recording if it is missing or
+    /// invalid
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+
+        let bytes = match fs::read(path) {
+            Ok(b) => b,
+            Err(e) => {
+                error!("unable to read {}: {}", path.display(), e);
+                return Self::new();
+            }
+        };
+
+        match toml::from_slice(&bytes) {
+            Ok(r) => r,
+            Err(e) => {
+                error!("error parsing {}:\n{}", path.display(), e);
+                Self::new()
+            }
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+
+        let contents = match toml::to_string(self) {
+            Ok(c) => c,
+            Err(e) => {
+                error!("unable to serialise input recording: {}", e);
+                return;
+            }
+        };
+
+        if let Some(parent) = path.parent()
+            && let Err(e) = fs::create_dir_all(parent)
+        {
+            error!("unable to create {}: {}", parent.display(), e);
+            return;
+        }
+
+        if let Err(e) = fs::write(path, contents) {
+            error!("unable to write {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn frames(&self) -> &[RecordedFrame] {
+        &self.frames
+    }
+
+    pub fn push(&mut self, frame: RecordedFrame) {
+        self.frames.push(frame);
+    }
+}
+
+/// The state of every action in a single frame. Presses and releases are worked out from the
+/// change in `pressed` between frames.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordedFrame {
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pressed: BTreeSet<String>,
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    triggered: BTreeSet<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    values: BTreeMap<String, f32>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    axes: BTreeMap<String, f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mouse: Option<Vec2>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mouse_world: Option<Vec2>,
+    #[serde(skip_serializing_if = "is_zero_vec2")]
+    mouse_delta: Vec2,
+    #[serde(skip_serializing_if = "is_zero_vec2")]
+    mouse_world_delta: Vec2,
+    #[serde(skip_serializing_if = "is_zero_vec2")]
+    gesture_world_delta: Vec2,
+    #[serde(skip_serializing_if = "is_zero")]
+    scroll: f32,
+    /// How long the frame took in seconds, which replays step time by so that physics and
+    /// anything else driven by time plays out as recorded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delta: Option<f32>,
+}
+
+fn is_zero(value: &f32) -> bool {
+    *value == 0.0
+}
+
+fn is_zero_vec2(value: &Vec2) -> bool {
+    *value == Vec2::ZERO
+}
+
+impl RecordedFrame {
+    fn capture(
+        state: &InputState,
+        registry: &Registry<InputMapping>,
+        axis_registry: &Registry<AxisMapping>,
+        delta: f32,
+    ) -> Self {
+        let path = |id| registry.resolve(id).map(str::to_string);
+        let axis_path = |id| axis_registry.resolve(id).map(str::to_string);
+
+        Self {
+            pressed: state.pressed.iter().filter_map(|&id| path(id)).collect(),
+            triggered: state.triggered.iter().filter_map(|&id| path(id)).collect(),
+            values: state
+                .values
+                .iter()
+                .filter_map(|(&id, &value)| Some((path(id)?, value)))
+                .collect(),
+            axes: state
+                .axes
+                .iter()
+                .filter_map(|(&id, &value)| Some((axis_path(id)?, value)))
+                .collect(),
+            mouse: state.mouse,
+            mouse_world: state.mouse_world,
+            mouse_delta: state.mouse_delta,
+            mouse_world_delta: state.mouse_world_delta,
+            gesture_world_delta: state.gesture_world_delta,
+            scroll: state.scroll,
+            delta: Some(delta),
+        }
+    }
+
+    /// Sets `state` to match the frame, pressing and releasing actions that changed since the
+    /// last frame. Paths that aren't registered are skipped.
+    fn apply(
+        &self,
+        state: &mut InputState,
+        registry: &Registry<InputMapping>,
+        axis_registry: &Registry<AxisMapping>,
+    ) {
+        state.clear();
+
+        let pressed: HashSet<_> = self
+            .pressed
+            .iter()
+            .filter_map(|path| registry.lookup(path))
+            .collect();
+        for &id in state.pressed.clone().difference(&pressed) {
+            state.release(id);
+        }
+        for &id in pressed.iter() {
+            if !state.pressed(id) {
+                state.press(id);
+            }
+        }
+
+        state.triggered = self
+            .triggered
+            .iter()
+            .filter_map(|path| registry.lookup(path))
+            .collect();
+        for (path, &value) in self.values.iter() {
+            if let Some(id) = registry.lookup(path) {
+                state.values.insert(id, value);
+            }
+        }
+        for (path, &value) in self.axes.iter() {
+            if let Some(id) = axis_registry.lookup(path) {
+                state.set_axis(id, value);
+            }
+        }
+        state.mouse = self.mouse;
+        // Recorded cameras don't exist any more, so replayed input goes to the primary camera
+        state.cursor_camera = None;
+        state.mouse_world = self.mouse_world;
+        state.mouse_delta = self.mouse_delta;
+        state.mouse_world_delta = self.mouse_world_delta;
+        state.gesture_world_delta = self.gesture_world_delta;
+        state.scroll = self.scroll;
+    }
+
+    fn paths(&self) -> impl Iterator<Item = &String> {
+        self.pressed
+            .iter()
+            .chain(self.triggered.iter())
+            .chain(self.values.keys())
+    }
+}
+
+pub(super) fn record_input(
+    mut playback: ResMut<InputPlayback>,
+    state: Res<InputState>,
+    registry: Res<Registry<InputMapping>>,
+    axis_registry: Res<Registry<AxisMapping>>,
+    time: Res<Time<Real>>,
+) {
+    if let InputPlayback::Recording { recording, .. } = playback.as_mut() {
+        recording.push(RecordedFrame::capture(
+            &state,
+            &registry,
+            &axis_registry,
+            time.delta_secs(),
+        ));
+    }
+}
+
+pub(super) fn save_recording(mut exit: MessageReader<AppExit>, playback: Res<InputPlayback>) {
+    if exit.read().next().is_none() {
+        return;
+    }
+
+    if let InputPlayback::Recording { path, recording } = playback.as_ref() {
+        recording.save(path);
+    }
+}
+
+/// Steps time by the length of the frame about to be replayed. Recordings without frame lengths
+/// run on the clock.
+pub(super) fn replay_time(playback: Res<InputPlayback>, mut strategy: ResMut<TimeUpdateStrategy>) {
+    let InputPlayback::Replaying { recording, frame } = playback.as_ref() else {
+        return;
+    };
+
+    *strategy = match recording
+        .frames
+        .get(*frame)
+        .and_then(|recorded| recorded.delta)
+    {
+        Some(delta) => TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(delta)),
+        None => TimeUpdateStrategy::Automatic,
+    };
+}
+
+pub(super) fn replay_input(
+    mut playback: ResMut<InputPlayback>,
+    mut state: ResMut<InputState>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+    registry: Res<Registry<InputMapping>>,
+    axis_registry: Res<Registry<AxisMapping>>,
+    time: Res<Time<Real>>,
+) {
+    let InputPlayback::Replaying { recording, frame } = playback.as_mut() else {
+        return;
+    };
+
+    match recording.frames.get(*frame) {
+        Some(recorded) => {
+            recorded.apply(&mut state, &registry, &axis_registry);
+            *frame += 1;
+
+            // Keep the press timings going, so how long actions are held matches the recording.
+            // What fired was recorded, so it is kept rather than worked out again.
+            let triggered = std::mem::take(&mut state.triggered);
+            let ids = registry.iter().map(|(&id, _)| id);
+            update_triggers(&mut state, ids, &registry, time.elapsed_secs());
+            state.triggered = triggered;
+        }
+        None => {
+            // Let go of everything and hand control back to the player
+            RecordedFrame::default().apply(&mut state, &registry, &axis_registry);
+            info!("input replay finished after {} frames", frame);
+            *playback = InputPlayback::Live;
+            *strategy = TimeUpdateStrategy::Automatic;
+        }
+    }
+}
+
+/// Warns about actions in the recording that no loaded mod registers
+pub(super) fn check_replay(
+    playback: Res<InputPlayback>,
+    registry: Res<Registry<InputMapping>>,
+    axis_registry: Res<Registry<AxisMapping>>,
+) {
+    let InputPlayback::Replaying { recording, .. } = playback.as_ref() else {
+        return;
+    };
+
+    let mut missing = BTreeSet::new();
+    for frame in recording.frames.iter() {
+        missing.extend(frame.paths().filter(|path| registry.lookup(path).is_none()));
+        missing.extend(
+            frame
+                .axes
+                .keys()
+                .filter(|path| axis_registry.lookup(path).is_none()),
+        );
+    }
+    for path in missing {
+        warn!("replaying input for unregistered action `{}`", path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::{TimePlugin, TimeSystems};
+
+    use super::*;
+    use crate::input::Input;
+
+    const DELTA: f32 = 1.0 / 30.0;
+
+    /// Records a jump held for two frames, then replays it without any input devices
+    #[test]
+    fn replays_recorded_frames() {
+        let mut registry = Registry::<InputMapping>::new();
+        let jump = registry
+            .register(
+                "test::input::jump",
+                InputMapping::nameless(Input::key(KeyCode::Space)),
+            )
+            .unwrap();
+        let axis_registry = Registry::<AxisMapping>::new();
+
+        let mut recording = InputRecording::new();
+        let mut state = InputState::new();
+        state.press(jump);
+        state.mouse_world = Some(Vec2::new(1.0, 2.0));
+        for release in [false, false, true] {
+            if release {
+                state.release(jump);
+            }
+            recording.push(RecordedFrame::capture(
+                &state,
+                &registry,
+                &axis_registry,
+                DELTA,
+            ));
+            state.clear();
+        }
+
+        let mut app = App::new();
+        app.add_plugins(TimePlugin)
+            .insert_resource(InputState::new())
+            .insert_resource(registry)
+            .insert_resource(axis_registry)
+            .insert_resource(InputPlayback::replay(recording))
+            .add_systems(First, replay_time.run_if(replaying).before(TimeSystems))
+            .add_systems(PreUpdate, replay_input.run_if(replaying));
+
+        app.update();
+        let state = app.world().resource::<InputState>();
+        assert!(state.just_pressed(jump));
+        assert_eq!(state.mouse_world(), Some(Vec2::new(1.0, 2.0)));
+
+        app.update();
+        let state = app.world().resource::<InputState>();
+        assert!(state.pressed(jump) && !state.just_pressed(jump));
+        // Time starts on the first update, so the second is the first with a length
+        let delta = app.world().resource::<Time<Real>>().delta_secs();
+        assert!((delta - DELTA).abs() < 1e-6);
+        assert!((state.held_for(jump) - DELTA).abs() < 1e-6);
+
+        app.update();
+        let state = app.world().resource::<InputState>();
+        assert!(state.just_released(jump) && !state.pressed(jump));
+
+        app.update();
+        assert!(!app.world().resource::<InputPlayback>().is_replaying());
+    }
+}
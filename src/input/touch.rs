@@ -0,0 +1,93 @@
+use bevy::{
+    ecs::system::SystemParam,
+    input::{
+        gestures::{PanGesture, PinchGesture},
+        touch::{Touch, Touches},
+    },
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+/// Touch and trackpad gestures made this frame
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Gestures {
+    /// How far the fingers spread apart, as the log of the change in distance between them, so
+    /// pinching in and out by the same amount cancels out
+    pub pinch: f32,
+    /// How far two fingers, or a trackpad pan, moved in logical pixels
+    pub pan: Vec2,
+    /// Where the pan started, in the window
+    pub origin: Option<Vec2>,
+}
+
+impl Gestures {
+    pub fn axis(&self, axis: GestureAxis) -> f32 {
+        match axis {
+            GestureAxis::Pinch => self.pinch,
+            GestureAxis::PanX => self.pan.x,
+            GestureAxis::PanY => self.pan.y,
+        }
+    }
+}
+
+/// A value read from a gesture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GestureAxis {
+    Pinch,
+    PanX,
+    PanY,
+}
+
+/// The touch screen and trackpad gesture messages
+#[derive(SystemParam)]
+pub(super) struct TouchInput<'w, 's> {
+    touches: Res<'w, Touches>,
+    pinch: MessageReader<'w, 's, PinchGesture>,
+    pan: MessageReader<'w, 's, PanGesture>,
+}
+
+impl TouchInput<'_, '_> {
+    pub fn touches(&self) -> &Touches {
+        &self.touches
+    }
+
+    /// Returns the position of the only finger on the screen, so a single finger can act as the
+    /// cursor
+    pub fn single_touch(&self) -> Option<Vec2> {
+        let mut fingers = self.touches.iter();
+        match (fingers.next(), fingers.next()) {
+            (Some(finger), None) => Some(finger.position()),
+            _ => None,
+        }
+    }
+
+    /// Reads this frame's gestures. Trackpad gestures happen wherever `cursor` is.
+    pub fn read(&mut self, cursor: Option<Vec2>) -> Gestures {
+        let mut gestures = Gestures::default();
+
+        let mut fingers: Vec<&Touch> = self.touches.iter().collect();
+        fingers.sort_by_key(|touch| touch.id());
+        if let [a, b] = fingers[..] {
+            let before = a.previous_position().distance(b.previous_position());
+            let after = a.position().distance(b.position());
+            if before > 0.0 && after > 0.0 {
+                gestures.pinch = (after / before).ln();
+            }
+
+            let origin = a.previous_position().midpoint(b.previous_position());
+            gestures.pan = a.position().midpoint(b.position()) - origin;
+            gestures.origin = Some(origin);
+        }
+
+        for event in self.pinch.read() {
+            gestures.pinch += (1.0 + event.0).max(f32::EPSILON).ln();
+        }
+        for event in self.pan.read() {
+            gestures.pan += event.0;
+            gestures.origin = gestures.origin.or(cursor);
+        }
+
+        gestures
+    }
+}
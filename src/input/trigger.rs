@@ -0,0 +1,113 @@
+use std::collections::HashSet;
+
+use crate::{
+    input::{InputMapping, InputState},
+    modding::registry::{Id, Registry},
+};
+
+/// When a mapping fires, based on how long and how often its action is pressed. Check whether it
+/// fired with [`InputState::triggered`].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Trigger {
+    /// Fires as soon as the action is pressed
+    #[default]
+    Press,
+    /// Fires once the action has been held for `duration` seconds, as for a long press
+    Hold { duration: f32 },
+    /// Fires when the action is released within `max_duration` seconds of being pressed. Pair
+    /// with [`Trigger::Hold`] on the same binding to tell taps from long presses.
+    Tap { max_duration: f32 },
+    /// Fires when the action is pressed again within `window` seconds of the last press
+    DoubleTap { window: f32 },
+    /// Fires when pressed, then `rate` times a second once it has been held for `delay` seconds
+    Repeat { delay: f32, rate: f32 },
+}
+
+/// The press history of an action, used to evaluate its trigger
+#[derive(Debug, Default, Clone, Copy)]
+pub(super) struct Timing {
+    pressed_at: f32,
+    /// When the press before this one started, until it is used up by a double tap
+    previous_press: Option<f32>,
+    /// When a hold or repeat next fires
+    next_fire: Option<f32>,
+}
+
+impl Timing {
+    pub fn pressed_at(&self) -> f32 {
+        self.pressed_at
+    }
+}
+
+pub(super) fn update_triggers(
+    state: &mut InputState,
+    ids: impl Iterator<Item = Id>,
+    registry: &Registry<InputMapping>,
+    now: f32,
+) {
+    state.time = now;
+
+    let mut triggered = HashSet::new();
+    for id in ids {
+        let trigger = registry
+            .get(id)
+            .map(InputMapping::trigger)
+            .unwrap_or_default();
+        let timing = state.timings.entry(id).or_default();
+
+        if state.just_pressed.contains(&id) {
+            let is_double = match trigger {
+                Trigger::DoubleTap { window } => {
+                    timing.previous_press.is_some_and(|p| now - p <= window)
+                }
+                _ => false,
+            };
+
+            // A double tap uses up both presses, so a third press starts a new pair
+            timing.previous_press = if is_double { None } else { Some(now) };
+            timing.pressed_at = now;
+            timing.next_fire = match trigger {
+                Trigger::Hold { duration } => Some(now + duration),
+                Trigger::Repeat { delay, .. } => Some(now + delay),
+                _ => None,
+            };
+
+            let fires = match trigger {
+                Trigger::Press | Trigger::Repeat { .. } => true,
+                Trigger::DoubleTap { .. } => is_double,
+                Trigger::Hold { .. } | Trigger::Tap { .. } => false,
+            };
+            if fires {
+                triggered.insert(id);
+            }
+            continue;
+        }
+
+        if state.just_released.contains(&id) {
+            timing.next_fire = None;
+            if let Trigger::Tap { max_duration } = trigger
+                && now - timing.pressed_at <= max_duration
+            {
+                triggered.insert(id);
+            }
+            continue;
+        }
+
+        if let Some(next) = timing.next_fire
+            && state.pressed.contains(&id)
+            && now >= next
+        {
+            triggered.insert(id);
+            timing.next_fire = match trigger {
+                // Skip any repeats missed during a long frame rather than firing them all at once
+                Trigger::Repeat { rate, .. } if rate > 0.0 => {
+                    let interval = 1.0 / rate;
+                    Some(next + interval * ((now - next) / interval).floor() + interval)
+                }
+                _ => None,
+            };
+        }
+    }
+
+    state.triggered = triggered;
+}
@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+
+use crate::{
+    input::InputState,
+    joint::{Connection, JointKind, JointTool},
+};
+
+/// How many coils a spring is drawn with
+const SPRING_COILS: usize = 8;
+
+/// How far the coils of a spring stick out from its middle, in world units
+const SPRING_WIDTH: f32 = 3.0;
+
+/// The size of the markers drawn at joint anchors, in world units
+const MARKER_SIZE: f32 = 2.0;
+
+fn joint_color(kind: JointKind) -> Color {
+    match kind {
+        JointKind::Weld => Color::hsl(0.0, 0.0, 0.9),
+        JointKind::Hinge => Color::hsl(50.0, 1.0, 0.6),
+        JointKind::Spring => Color::hsl(120.0, 0.8, 0.5),
+        JointKind::Rope => Color::hsl(30.0, 0.6, 0.5),
+        JointKind::Slider => Color::hsl(190.0, 0.9, 0.6),
+    }
+}
+
+pub(super) fn draw_connections(
+    mut gizmos: Gizmos,
+    joints: Query<&Connection>,
+    bodies: Query<&GlobalTransform>,
+) {
+    for connection in joints.iter() {
+        let (Ok(global1), Ok(global2)) =
+            (bodies.get(connection.body1), bodies.get(connection.body2))
+        else {
+            continue;
+        };
+        let start = global1
+            .transform_point(connection.anchor1.extend(0.0))
+            .truncate();
+        let end = global2
+            .transform_point(connection.anchor2.extend(0.0))
+            .truncate();
+        let color = joint_color(connection.kind);
+
+        match connection.kind {
+            JointKind::Weld => {
+                gizmos.rect_2d(end, Vec2::splat(MARKER_SIZE * 2.0), color);
+            }
+            JointKind::Hinge => {
+                gizmos.circle_2d(end, MARKER_SIZE, color);
+            }
+            JointKind::Spring => {
+                gizmos.linestrip_2d(spring_points(start, end), color);
+            }
+            JointKind::Rope => {
+                gizmos.line_2d(start, end, color);
+            }
+            JointKind::Slider => {
+                gizmos.line_2d(start, end, color);
+                gizmos.circle_2d(start, MARKER_SIZE, color);
+                gizmos.rect_2d(end, Vec2::splat(MARKER_SIZE * 2.0), color);
+            }
+        }
+    }
+}
+
+/// Draws the joint being placed from its first point to the cursor. This is the joint tool's draw
+/// hook.
+pub fn preview_connection(mut gizmos: Gizmos, tool: Res<JointTool>, input: Res<InputState>) {
+    let Some(cursor) = input.mouse_world() else {
+        return;
+    };
+    let color = joint_color(tool.kind).with_alpha(0.6);
+
+    match tool.first {
+        Some((_, point)) => {
+            gizmos.line_2d(point, cursor, color);
+            gizmos.circle_2d(point, MARKER_SIZE, color);
+        }
+        None => {
+            gizmos.circle_2d(cursor, MARKER_SIZE, color);
+        }
+    }
+}
+
+/// The zigzag a spring is drawn as, with straight ends so it meets its anchors cleanly
+fn spring_points(start: Vec2, end: Vec2) -> Vec<Vec2> {
+    let along = end - start;
+    let side = along.perp().normalize_or_zero() * SPRING_WIDTH;
+
+    let mut points = vec![start, start + along * 0.1];
+    for i in 0..SPRING_COILS * 2 {
+        let t = 0.1 + 0.8 * (i as f32 + 0.5) / (SPRING_COILS * 2) as f32;
+        let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+        points.push(start + along * t + side * sign);
+    }
+    points.push(start + along * 0.9);
+    points.push(end);
+    points
+}
@@ -0,0 +1,447 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Cursor,
+    camera::effects::CameraEffect,
+    history::{self, edits::JointEdit},
+    input::{InputMapping, InputState},
+    joint::gizmos::draw_connections,
+    modding::{
+        PostModLoad,
+        registry::{Id, Registry},
+    },
+};
+
+pub mod gizmos;
+
+/// Lets the player connect objects to each other, or to the world, with physics joints using the
+/// joint tool.
+pub struct JointPlugin;
+
+impl Plugin for JointPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<JointTool>()
+            .add_systems(PostModLoad, init_joint_input_ids)
+            .add_systems(
+                Update,
+                (break_joints, remove_orphaned_joints, draw_connections).chain(),
+            );
+    }
+}
+
+/// How stretchy a spring is. Higher values make it softer.
+const SPRING_COMPLIANCE: f32 = 1e-3;
+
+/// How much the camera shakes when a joint snaps
+const BREAK_TRAUMA: f32 = 0.3;
+
+/// A kind of connection between two objects
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JointKind {
+    /// Holds both objects rigidly together
+    #[default]
+    Weld,
+    /// Lets the objects turn around a shared point
+    Hinge,
+    /// Pulls the objects back to the distance they were connected at
+    Spring,
+    /// Stops the objects moving further apart than they were connected at
+    Rope,
+    /// Lets the second object slide along the line between the two points
+    Slider,
+}
+
+impl JointKind {
+    pub const ALL: [JointKind; 5] = [
+        JointKind::Weld,
+        JointKind::Hinge,
+        JointKind::Spring,
+        JointKind::Rope,
+        JointKind::Slider,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            JointKind::Weld => "Weld",
+            JointKind::Hinge => "Hinge",
+            JointKind::Spring => "Spring",
+            JointKind::Rope => "Rope",
+            JointKind::Slider => "Slider",
+        }
+    }
+
+    /// How much force and torque a new joint of this kind takes to break
+    pub fn default_strength(&self) -> JointStrength {
+        match self {
+            JointKind::Weld => JointStrength::new(2e6, 2e7),
+            JointKind::Hinge => JointStrength::new(2e6, f32::INFINITY),
+            JointKind::Spring => JointStrength::new(1e6, f32::INFINITY),
+            JointKind::Rope => JointStrength::new(5e5, f32::INFINITY),
+            JointKind::Slider => JointStrength::new(1e6, 1e7),
+        }
+    }
+
+    /// Whether the connected objects pass through each other, as they would otherwise push
+    /// apart where they overlap
+    fn disables_collision(&self) -> bool {
+        matches!(self, JointKind::Weld | JointKind::Hinge | JointKind::Slider)
+    }
+}
+
+/// A joint placed by the player, which is kept on the joint's entity
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Connection {
+    pub kind: JointKind,
+    pub body1: Entity,
+    pub body2: Entity,
+    /// Where the joint attaches to each body, relative to the body
+    pub anchor1: Vec2,
+    pub anchor2: Vec2,
+    /// How far apart the points were when connected, which springs and ropes keep to
+    pub length: f32,
+    /// The direction a slider slides in, relative to the first body
+    pub axis: Vec2,
+    /// The static body made to attach to the world, which goes with the joint
+    pub world_anchor: Option<Entity>,
+}
+
+impl Connection {
+    /// Returns the same connection between two other bodies, such as copies of the original ones
+    pub fn with_bodies(mut self, body1: Entity, body2: Entity) -> Self {
+        self.body1 = body1;
+        self.body2 = body2;
+        self.world_anchor = None;
+        self
+    }
+}
+
+/// A joint as it was at one moment, which can be put back
+#[derive(Debug, Clone, Copy)]
+pub struct JointSnapshot {
+    pub connection: Connection,
+    pub strength: JointStrength,
+    /// Where the world anchor was, for joints attached to the world
+    pub world_anchor: Option<Vec2>,
+}
+
+impl JointSnapshot {
+    /// Returns how the joint `entity` is now
+    pub fn capture(world: &World, entity: Entity) -> Option<Self> {
+        let entity = world.get_entity(entity).ok()?;
+        let connection = *entity.get::<Connection>()?;
+        let world_anchor = connection
+            .world_anchor
+            .and_then(|anchor| world.get::<Transform>(anchor))
+            .map(|transform| transform.translation.truncate());
+        Some(Self {
+            connection,
+            strength: *entity.get::<JointStrength>()?,
+            world_anchor,
+        })
+    }
+
+    /// Spawns the joint again between the bodies `bodies` gives for the original ones. An end
+    /// attached to the world gets a new anchor where the old one was.
+    pub fn spawn(&self, commands: &mut Commands, bodies: impl Fn(Entity) -> Entity) -> Entity {
+        let Connection {
+            body1,
+            body2,
+            world_anchor,
+            ..
+        } = self.connection;
+
+        let mut anchor = None;
+        let mut end = |body: Entity| match self.world_anchor {
+            Some(position) if Some(body) == world_anchor => {
+                let transform = Transform::from_translation(position.extend(0.0));
+                let entity = commands.spawn((RigidBody::Static, transform)).id();
+                anchor = Some(entity);
+                entity
+            }
+            _ => bodies(body),
+        };
+        let (body1, body2) = (end(body1), end(body2));
+
+        let mut connection = self.connection.with_bodies(body1, body2);
+        connection.world_anchor = anchor;
+        spawn_joint(commands, connection, self.strength)
+    }
+}
+
+/// The force and torque that break a joint when exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub struct JointStrength {
+    pub max_force: f32,
+    pub max_torque: f32,
+}
+
+impl JointStrength {
+    pub fn new(max_force: f32, max_torque: f32) -> Self {
+        Self {
+            max_force,
+            max_torque,
+        }
+    }
+}
+
+/// The joint the player is placing, and the first point of it once clicked
+#[derive(Debug, Default, Resource)]
+pub struct JointTool {
+    pub kind: JointKind,
+    /// The body clicked first, or none for the world, and the point clicked on it
+    first: Option<(Option<Entity>, Vec2)>,
+}
+
+impl JointTool {
+    /// Picks the kind of joint to place, forgetting any half placed one
+    pub fn set_kind(&mut self, kind: JointKind) {
+        self.kind = kind;
+        self.first = None;
+    }
+}
+
+#[derive(Debug, Resource)]
+pub(crate) struct JointInputIds {
+    select: Id,
+    cancel: Id,
+    tool_mode: Id,
+}
+
+fn init_joint_input_ids(mut commands: Commands, inputs: Res<Registry<InputMapping>>) {
+    let ids = JointInputIds {
+        select: inputs.lookup("base::input::select").unwrap(),
+        cancel: inputs.lookup("base::input::cancel").unwrap(),
+        tool_mode: inputs.lookup("base::input::tool_mode").unwrap(),
+    };
+    commands.insert_resource(ids);
+}
+
+/// Connects the first body clicked to the second, or to the world where nothing was clicked.
+/// This is the joint tool's update hook.
+pub fn place_joint(
+    mut commands: Commands,
+    mut tool: ResMut<JointTool>,
+    input: Res<InputState>,
+    ids: Res<JointInputIds>,
+    spatial_query: SpatialQuery,
+    bodies: Query<&GlobalTransform, With<RigidBody>>,
+    cursor: Query<Entity, With<Cursor>>,
+) {
+    // The tool's mode steps through the kinds of joint
+    if input.just_pressed(ids.tool_mode) {
+        let index = JointKind::ALL.iter().position(|&k| k == tool.kind);
+        let next = index.map_or(0, |i| (i + 1) % JointKind::ALL.len());
+        tool.set_kind(JointKind::ALL[next]);
+        info!("placing {} joints", tool.kind.name().to_lowercase());
+    }
+    if input.just_pressed(ids.cancel) {
+        tool.first = None;
+    }
+
+    if !input.just_pressed(ids.select) {
+        return;
+    }
+    let Some(point) = input.mouse_world() else {
+        return;
+    };
+
+    let filter = SpatialQueryFilter::default().with_excluded_entities(cursor.iter());
+    let body = spatial_query
+        .point_intersections(point, &filter)
+        .into_iter()
+        .find(|&entity| bodies.contains(entity));
+
+    let Some((first, first_point)) = tool.first else {
+        tool.first = Some((body, point));
+        return;
+    };
+    tool.first = None;
+
+    // A joint needs something to move, and can't connect a body to itself. The first body may
+    // also have gone since it was clicked.
+    if first == body || first.is_some_and(|entity| !bodies.contains(entity)) {
+        return;
+    }
+
+    // Ends attached to the world get a static body of their own to hold on to
+    let mut world_anchor = None;
+    let mut end = |body: Option<Entity>, clicked: Vec2| {
+        if let Some(global) = body.and_then(|entity| bodies.get(entity).ok()) {
+            return (body.unwrap(), *global);
+        }
+        let transform = Transform::from_translation(clicked.extend(0.0));
+        let anchor = commands.spawn((RigidBody::Static, transform)).id();
+        world_anchor = Some(anchor);
+        (anchor, GlobalTransform::from(transform))
+    };
+    let end1 = end(first, first_point);
+    let end2 = end(body, point);
+
+    let joint = spawn_connection(
+        &mut commands,
+        tool.kind,
+        end1,
+        end2,
+        (first_point, point),
+        world_anchor,
+    );
+    history::record(&mut commands, JointEdit::placed(joint));
+}
+
+/// Spawns a joint between two bodies, given with their transforms, from the first point to the
+/// second. Welds and hinges join the bodies at the second point.
+fn spawn_connection(
+    commands: &mut Commands,
+    kind: JointKind,
+    (body1, global1): (Entity, GlobalTransform),
+    (body2, global2): (Entity, GlobalTransform),
+    (point1, point2): (Vec2, Vec2),
+    world_anchor: Option<Entity>,
+) -> Entity {
+    let to_local = |global: &GlobalTransform, point: Vec2| {
+        global
+            .affine()
+            .inverse()
+            .transform_point3(point.extend(0.0))
+            .truncate()
+    };
+    let (anchor1, anchor2) = match kind {
+        JointKind::Weld | JointKind::Hinge => {
+            (to_local(&global1, point2), to_local(&global2, point2))
+        }
+        _ => (to_local(&global1, point1), to_local(&global2, point2)),
+    };
+    let length = point1.distance(point2);
+
+    // The axis is kept in the first body's frame, so it turns with the body
+    let axis = (point2 - point1).try_normalize().unwrap_or(Vec2::X);
+    let axis = global1
+        .affine()
+        .inverse()
+        .transform_vector3(axis.extend(0.0))
+        .truncate()
+        .normalize_or(Vec2::X);
+
+    let connection = Connection {
+        kind,
+        body1,
+        body2,
+        anchor1,
+        anchor2,
+        length,
+        axis,
+        world_anchor,
+    };
+    spawn_joint(commands, connection, kind.default_strength())
+}
+
+/// Spawns the physics joint for a connection, which breaks when pulled harder than `strength`
+pub fn spawn_joint(
+    commands: &mut Commands,
+    connection: Connection,
+    strength: JointStrength,
+) -> Entity {
+    let Connection {
+        kind,
+        body1,
+        body2,
+        anchor1,
+        anchor2,
+        length,
+        axis,
+        ..
+    } = connection;
+
+    let mut entity = commands.spawn((connection, strength, JointForces::default()));
+    match kind {
+        JointKind::Weld => {
+            entity.insert(
+                FixedJoint::new(body1, body2)
+                    .with_local_anchor1(anchor1)
+                    .with_local_anchor2(anchor2),
+            );
+        }
+        JointKind::Hinge => {
+            entity.insert(
+                RevoluteJoint::new(body1, body2)
+                    .with_local_anchor1(anchor1)
+                    .with_local_anchor2(anchor2),
+            );
+        }
+        JointKind::Spring => {
+            entity.insert(
+                DistanceJoint::new(body1, body2)
+                    .with_local_anchor1(anchor1)
+                    .with_local_anchor2(anchor2)
+                    .with_limits(length, length)
+                    .with_compliance(SPRING_COMPLIANCE),
+            );
+        }
+        JointKind::Rope => {
+            entity.insert(
+                DistanceJoint::new(body1, body2)
+                    .with_local_anchor1(anchor1)
+                    .with_local_anchor2(anchor2)
+                    .with_limits(0.0, length),
+            );
+        }
+        JointKind::Slider => {
+            entity.insert(
+                PrismaticJoint::new(body1, body2)
+                    .with_local_anchor1(anchor1)
+                    .with_local_anchor2(anchor2)
+                    .with_free_axis(axis),
+            );
+        }
+    }
+    if kind.disables_collision() {
+        entity.insert(JointCollisionDisabled);
+    }
+    entity.id()
+}
+
+/// Removes a connection along with the world anchor made for it
+pub fn despawn_connection(commands: &mut Commands, entity: Entity, connection: &Connection) {
+    commands.entity(entity).despawn();
+    if let Some(anchor) = connection.world_anchor {
+        commands.entity(anchor).despawn();
+    }
+}
+
+/// Forgets a half placed joint when switching away from the joint tool
+pub fn cancel_joint(mut tool: ResMut<JointTool>) {
+    tool.first = None;
+}
+
+/// Breaks joints pulled or twisted harder than they can take
+fn break_joints(
+    mut commands: Commands,
+    joints: Query<(Entity, &Connection, &JointStrength, &JointForces)>,
+    mut effects: MessageWriter<CameraEffect>,
+) {
+    for (entity, connection, strength, forces) in joints.iter() {
+        let force = forces.force().length();
+        let torque = forces.torque().abs();
+        if force > strength.max_force || torque > strength.max_torque {
+            debug!("{} joint broke", connection.kind.name());
+            despawn_connection(&mut commands, entity, connection);
+            effects.write(CameraEffect::Trauma(BREAK_TRAUMA));
+        }
+    }
+}
+
+/// Removes joints left without one of their bodies
+fn remove_orphaned_joints(
+    mut commands: Commands,
+    joints: Query<(Entity, &Connection)>,
+    bodies: Query<(), With<RigidBody>>,
+) {
+    for (entity, connection) in joints.iter() {
+        if !bodies.contains(connection.body1) || !bodies.contains(connection.body2) {
+            despawn_connection(&mut commands, entity, connection);
+        }
+    }
+}
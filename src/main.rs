@@ -5,18 +5,62 @@ use bevy::{
 };
 
 use crate::{
-    camera::CameraPlugin,
-    input::{Input, InputMapping, InputPlugin, InputState},
-    modding::{ModLoad, ModPlugin, registry::Registry},
+    camera::{
+        CameraPlugin,
+        bookmarks::{self, BOOKMARK_COUNT},
+    },
+    grab::GrabPlugin,
+    history::HistoryPlugin,
+    input::{
+        Input, InputMapping, InputPlugin, InputState,
+        analog::AxisDirection,
+        axis::{AxisBinding, AxisMapping, MouseAxis},
+        context::{ContextMode, InputContext},
+        record::InputPlayback,
+        touch::GestureAxis,
+        trigger::Trigger,
+    },
+    joint::JointPlugin,
+    modding::{
+        ModLoad, ModPlugin, PostModLoad,
+        registry::{Id, Registry},
+    },
+    save::SavePlugin,
+    selection::SelectionPlugin,
+    settings::SettingsPlugin,
+    spawn::{SpawnPlugin, SpawnShape, Spawnable},
+    tool::{Tool, ToolPlugin, builtin},
 };
 
 mod camera;
+mod grab;
+mod history;
 mod input;
+mod joint;
 mod modding;
+mod save;
+mod selection;
+mod settings;
+mod spawn;
+mod tool;
 
 /// The number of pixels to a metre
 const UNIT: usize = 16;
 
+/// The number keys along the top of the keyboard, indexed by their digit
+const DIGIT_KEYS: [KeyCode; 10] = [
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
 fn main() -> AppExit {
     App::new()
         .add_plugins((
@@ -38,58 +82,450 @@ fn main() -> AppExit {
             ModPlugin,
             InputPlugin,
             CameraPlugin,
+            SettingsPlugin,
+            SelectionPlugin,
+            SpawnPlugin,
+            GrabPlugin,
+            JointPlugin,
+            ToolPlugin,
+            HistoryPlugin,
+            SavePlugin,
         ))
+        .insert_resource(InputPlayback::from_args())
+        .add_systems(PostModLoad, init_main_input_ids)
         .add_systems(Startup, setup)
         .add_systems(Update, (esc_exit, cursor_system))
         // Temporary mod loading
-        .add_systems(ModLoad, reg_setup)
+        .add_systems(ModLoad, (reg_setup, reg_tools))
         .run()
 }
 
-fn reg_setup(mut input: ResMut<Registry<InputMapping>>) {
+fn reg_setup(
+    mut contexts: ResMut<Registry<InputContext>>,
+    mut input: ResMut<Registry<InputMapping>>,
+    mut axes: ResMut<Registry<AxisMapping>>,
+    mut spawnables: ResMut<Registry<Spawnable>>,
+) {
+    let sandbox = contexts
+        .register(
+            "base::context::sandbox",
+            InputContext::new("Sandbox", 0, ContextMode::PassThrough)
+                .active_by_default()
+                .world(),
+        )
+        .unwrap();
+    let menu = contexts
+        .register(
+            "base::context::menu",
+            InputContext::new("Menu", 100, ContextMode::Block),
+        )
+        .unwrap();
+
     input
-        .register("base::input::up", Input::key(KeyCode::KeyW).into())
+        .register(
+            "base::input::up",
+            InputMapping::new("Move Up", Input::key(KeyCode::KeyW))
+                .with_alternative(Input::key(KeyCode::ArrowUp))
+                .with_alternative(Input::axis(
+                    GamepadAxis::LeftStickY,
+                    AxisDirection::Positive,
+                ))
+                .in_context(sandbox),
+        )
         .unwrap();
     input
-        .register("base::input::down", Input::key(KeyCode::KeyS).into())
+        .register(
+            "base::input::down",
+            InputMapping::new("Move Down", Input::key(KeyCode::KeyS))
+                .with_alternative(Input::key(KeyCode::ArrowDown))
+                .with_alternative(Input::axis(
+                    GamepadAxis::LeftStickY,
+                    AxisDirection::Negative,
+                ))
+                .in_context(sandbox),
+        )
         .unwrap();
     input
-        .register("base::input::left", Input::key(KeyCode::KeyA).into())
+        .register(
+            "base::input::left",
+            InputMapping::new("Move Left", Input::key(KeyCode::KeyA))
+                .with_alternative(Input::key(KeyCode::ArrowLeft))
+                .with_alternative(Input::axis(
+                    GamepadAxis::LeftStickX,
+                    AxisDirection::Negative,
+                ))
+                .in_context(sandbox),
+        )
         .unwrap();
     input
-        .register("base::input::right", Input::key(KeyCode::KeyD).into())
+        .register(
+            "base::input::right",
+            InputMapping::new("Move Right", Input::key(KeyCode::KeyD))
+                .with_alternative(Input::key(KeyCode::ArrowRight))
+                .with_alternative(Input::axis(
+                    GamepadAxis::LeftStickX,
+                    AxisDirection::Positive,
+                ))
+                .in_context(sandbox),
+        )
         .unwrap();
 
     input
-        .register("base::input::speed", Input::key(KeyCode::ShiftLeft).into())
+        .register(
+            "base::input::speed",
+            InputMapping::new("Move Faster", Input::key(KeyCode::ShiftLeft))
+                .with_alternative(Input::gamepad(GamepadButton::LeftTrigger2))
+                .in_context(sandbox),
+        )
         .unwrap();
 
     input
         .register(
             "base::input::zoom_in",
-            Input::key(KeyCode::Equal).with_lshift().into(),
+            InputMapping::new("Zoom In", Input::key(KeyCode::Equal).with_lshift())
+                .with_trigger(Trigger::Repeat {
+                    delay: 0.4,
+                    rate: 8.0,
+                })
+                .in_context(sandbox),
         )
         .unwrap();
     input
         .register(
             "base::input::zoom_out",
-            Input::key(KeyCode::Minus).with_lshift().into(),
+            InputMapping::new("Zoom Out", Input::key(KeyCode::Minus).with_lshift())
+                .with_trigger(Trigger::Repeat {
+                    delay: 0.4,
+                    rate: 8.0,
+                })
+                .in_context(sandbox),
         )
         .unwrap();
 
     input
-        .register("base::input::pan", Input::mouse(MouseButton::Middle).into())
+        .register(
+            "base::input::pan",
+            InputMapping::new("Pan", Input::mouse(MouseButton::Middle)).in_context(sandbox),
+        )
         .unwrap();
 
+    input
+        .register(
+            "base::input::focus",
+            InputMapping::new("Focus Selection", Input::key(KeyCode::KeyF)).in_context(sandbox),
+        )
+        .unwrap();
+    input
+        .register(
+            "base::input::follow",
+            InputMapping::new("Follow Selection", Input::key(KeyCode::KeyF).with_lshift())
+                .in_context(sandbox),
+        )
+        .unwrap();
+    input
+        .register(
+            "base::input::minimap",
+            InputMapping::new("Toggle Minimap", Input::key(KeyCode::KeyM)).in_context(sandbox),
+        )
+        .unwrap();
+
+    for slot in 1..=BOOKMARK_COUNT {
+        let key = DIGIT_KEYS[slot as usize];
+        input
+            .register(
+                &bookmarks::recall_path(slot),
+                InputMapping::new(&format!("Go To Bookmark {slot}"), Input::key(key))
+                    .in_context(sandbox),
+            )
+            .unwrap();
+        input
+            .register(
+                &bookmarks::save_path(slot),
+                InputMapping::new(
+                    &format!("Save Bookmark {slot}"),
+                    Input::key(key).with_lctrl(),
+                )
+                .in_context(sandbox),
+            )
+            .unwrap();
+    }
+
     input
         .register(
             "base::input::select",
-            Input::mouse(MouseButton::Left).into(),
+            InputMapping::new("Select", Input::mouse(MouseButton::Left))
+                .with_alternative(Input::touch(1))
+                .in_context(sandbox),
+        )
+        .unwrap();
+
+    // Held while selecting rather than chorded with the click, so pressing it partway through a
+    // drag doesn't take over from select and drop the drag
+    input
+        .register(
+            "base::input::multi_select",
+            InputMapping::new("Add To Selection", Input::key(KeyCode::ControlLeft))
+                .in_context(sandbox),
+        )
+        .unwrap();
+
+    input
+        .register(
+            "base::input::copy",
+            InputMapping::new("Copy", Input::key(KeyCode::KeyC).with_lctrl()).in_context(sandbox),
+        )
+        .unwrap();
+    input
+        .register(
+            "base::input::paste",
+            InputMapping::new("Paste", Input::key(KeyCode::KeyV).with_lctrl()).in_context(sandbox),
+        )
+        .unwrap();
+    input
+        .register(
+            "base::input::duplicate",
+            InputMapping::new("Duplicate", Input::key(KeyCode::KeyD).with_lctrl())
+                .in_context(sandbox),
+        )
+        .unwrap();
+
+    input
+        .register(
+            "base::input::undo",
+            InputMapping::new("Undo", Input::key(KeyCode::KeyZ).with_lctrl()).in_context(sandbox),
+        )
+        .unwrap();
+    input
+        .register(
+            "base::input::redo",
+            InputMapping::new("Redo", Input::key(KeyCode::KeyZ).with_lctrl().with_lshift())
+                .in_context(sandbox),
+        )
+        .unwrap();
+
+    input
+        .register(
+            "base::input::quick_save",
+            InputMapping::new("Quick Save", Input::key(KeyCode::F5)).in_context(sandbox),
+        )
+        .unwrap();
+    input
+        .register(
+            "base::input::quick_load",
+            InputMapping::new("Quick Load", Input::key(KeyCode::F9)).in_context(sandbox),
+        )
+        .unwrap();
+
+    input
+        .register(
+            "base::input::freeze",
+            InputMapping::new("Freeze", Input::key(KeyCode::KeyE)).in_context(sandbox),
+        )
+        .unwrap();
+
+    input
+        .register(
+            "base::input::tool_mode",
+            InputMapping::new("Cycle Tool Mode", Input::key(KeyCode::KeyV).exact())
+                .in_context(sandbox),
+        )
+        .unwrap();
+
+    input
+        .register(
+            "base::input::next_tool",
+            InputMapping::new("Next Tool", Input::key(KeyCode::KeyX).exact()).in_context(sandbox),
+        )
+        .unwrap();
+
+    input
+        .register(
+            "base::input::previous_tool",
+            InputMapping::new("Previous Tool", Input::key(KeyCode::KeyZ).exact())
+                .in_context(sandbox),
+        )
+        .unwrap();
+
+    input
+        .register(
+            "base::input::cancel",
+            InputMapping::new("Cancel", Input::mouse(MouseButton::Right)).in_context(sandbox),
+        )
+        .unwrap();
+
+    input
+        .register(
+            "base::input::spawn_menu",
+            InputMapping::new("Spawn Menu", Input::key(KeyCode::Tab)).in_context(sandbox),
+        )
+        .unwrap();
+
+    input
+        .register(
+            "base::input::exit",
+            InputMapping::new("Exit", Input::key(KeyCode::Escape)).in_context(sandbox),
+        )
+        .unwrap();
+
+    input
+        .register(
+            "base::input::back",
+            InputMapping::new("Back", Input::key(KeyCode::Escape)).in_context(menu),
+        )
+        .unwrap();
+
+    input
+        .register(
+            "base::input::settings",
+            InputMapping::new("Settings", Input::key(KeyCode::F1)),
+        )
+        .unwrap();
+
+    axes.register(
+        "base::input::zoom",
+        AxisMapping::new("Zoom", AxisBinding::wheel(MouseAxis::Y))
+            .with_alternative(AxisBinding::gesture(GestureAxis::Pinch).with_scale(3.5))
+            .in_context(sandbox),
+    )
+    .unwrap();
+
+    spawnables
+        .register(
+            "base::spawnable::box",
+            Spawnable::new(
+                "Box",
+                SpawnShape::Rectangle {
+                    width: 16.0,
+                    height: 16.0,
+                },
+            )
+            .in_category("Shapes")
+            .with_color(Color::hsl(0.0, 1.0, 0.5)),
+        )
+        .unwrap();
+    spawnables
+        .register(
+            "base::spawnable::ball",
+            Spawnable::new("Ball", SpawnShape::Circle { radius: 8.0 })
+                .in_category("Shapes")
+                .with_color(Color::hsl(210.0, 1.0, 0.5))
+                .with_restitution(0.6),
+        )
+        .unwrap();
+    spawnables
+        .register(
+            "base::spawnable::capsule",
+            Spawnable::new(
+                "Capsule",
+                SpawnShape::Capsule {
+                    radius: 6.0,
+                    length: 12.0,
+                },
+            )
+            .in_category("Shapes")
+            .with_color(Color::hsl(280.0, 0.8, 0.6)),
+        )
+        .unwrap();
+    spawnables
+        .register(
+            "base::spawnable::plank",
+            Spawnable::new(
+                "Plank",
+                SpawnShape::Rectangle {
+                    width: 64.0,
+                    height: 4.0,
+                },
+            )
+            .in_category("Structure")
+            .with_color(Color::hsl(30.0, 0.6, 0.4))
+            .with_friction(0.8),
+        )
+        .unwrap();
+    spawnables
+        .register(
+            "base::spawnable::platform",
+            Spawnable::new(
+                "Platform",
+                SpawnShape::Rectangle {
+                    width: 256.0,
+                    height: 16.0,
+                },
+            )
+            .in_category("Structure")
+            .with_body(RigidBody::Static)
+            .with_color(Color::hsl(90.0, 1.0, 0.5)),
         )
         .unwrap();
 }
 
-fn setup(mut commands: Commands) {
+fn reg_tools(mut commands: Commands, mut tools: ResMut<Registry<Tool>>) {
+    tools
+        .register(
+            "base::tool::select",
+            Tool::new("Select")
+                .on_deactivate(commands.register_system(selection::end_select_drag))
+                .on_update(commands.register_system(selection::click_select))
+                .on_draw(commands.register_system(selection::gizmos::draw_select_tool)),
+        )
+        .unwrap();
+    tools
+        .register(
+            "base::tool::spawn",
+            Tool::new("Spawn")
+                .on_activate(commands.register_system(spawn::palette::open_palette))
+                .on_deactivate(commands.register_system(spawn::palette::close_palette))
+                .on_update(commands.register_system(spawn::place_spawnable))
+                .on_draw(commands.register_system(spawn::preview_placement)),
+        )
+        .unwrap();
+    tools
+        .register(
+            "base::tool::drag",
+            Tool::new("Drag")
+                .on_deactivate(commands.register_system(grab::drop_grab))
+                .on_update(commands.register_system(grab::start_grab)),
+        )
+        .unwrap();
+    tools
+        .register(
+            "base::tool::delete",
+            Tool::new("Delete").on_update(commands.register_system(builtin::delete_tool)),
+        )
+        .unwrap();
+    tools
+        .register(
+            "base::tool::joint",
+            Tool::new("Joint")
+                .on_deactivate(commands.register_system(joint::cancel_joint))
+                .on_update(commands.register_system(joint::place_joint))
+                .on_draw(commands.register_system(joint::gizmos::preview_connection)),
+        )
+        .unwrap();
+    tools
+        .register(
+            "base::tool::freeze",
+            Tool::new("Freeze").on_update(commands.register_system(builtin::freeze_tool)),
+        )
+        .unwrap();
+    tools
+        .register(
+            "base::tool::resize",
+            Tool::new("Resize")
+                .on_deactivate(commands.register_system(builtin::end_resize))
+                .on_update(commands.register_system(builtin::resize_tool)),
+        )
+        .unwrap();
+    tools
+        .register(
+            "base::tool::paint",
+            Tool::new("Paint")
+                .on_update(commands.register_system(builtin::paint_tool))
+                .on_draw(commands.register_system(builtin::preview_paint)),
+        )
+        .unwrap();
+}
+
+fn setup(mut commands: Commands, spawnables: Res<Registry<Spawnable>>, assets: Res<AssetServer>) {
     commands.spawn((
         Cursor,
         Sprite::from_color(Color::hsl(0.0, 0.0, 1.0), Vec2::ONE),
@@ -97,25 +533,35 @@ fn setup(mut commands: Commands) {
         Collider::rectangle(1.0, 1.0),
     ));
 
-    // Red physics box
-    commands.spawn((
-        Transform::from_xyz(0.0, 0.0, 0.0),
-        RigidBody::Dynamic,
-        Collider::rectangle(16.0, 16.0),
-        Sprite::from_color(Color::hsl(0.0, 1.0, 0.5), Vec2::new(16.0, 16.0)),
-    ));
+    // A box dropping onto a platform
+    let scene = [
+        ("base::spawnable::box", Vec2::ZERO),
+        ("base::spawnable::platform", Vec2::new(0.0, -128.0)),
+    ];
+    for (path, position) in scene {
+        let Some(id) = spawnables.lookup(path) else {
+            continue;
+        };
+        if let Some(spawnable) = spawnables.get(id) {
+            spawnable.spawn(&mut commands, &assets, id, position);
+        }
+    }
+}
 
-    // Platform
-    commands.spawn((
-        Transform::from_xyz(0.0, -128.0, 0.0),
-        RigidBody::Static,
-        Collider::rectangle(256.0, 16.0),
-        Sprite::from_color(Color::hsl(90.0, 1.0, 0.5), Vec2::new(256.0, 16.0)),
-    ));
+#[derive(Debug, Resource)]
+struct MainInputIds {
+    exit: Id,
 }
 
-fn esc_exit(input: Res<ButtonInput<KeyCode>>, mut exit: MessageWriter<AppExit>) {
-    if input.just_pressed(KeyCode::Escape) {
+fn init_main_input_ids(mut commands: Commands, inputs: Res<Registry<InputMapping>>) {
+    let ids = MainInputIds {
+        exit: inputs.lookup("base::input::exit").unwrap(),
+    };
+    commands.insert_resource(ids);
+}
+
+fn esc_exit(input: Res<InputState>, ids: Res<MainInputIds>, mut exit: MessageWriter<AppExit>) {
+    if input.just_pressed(ids.exit) {
         exit.write(AppExit::Success);
     }
 }
@@ -124,19 +570,12 @@ fn esc_exit(input: Res<ButtonInput<KeyCode>>, mut exit: MessageWriter<AppExit>)
 #[require(Transform)]
 struct Cursor;
 
-fn cursor_system(
-    mut cursor: Query<&mut Transform, With<Cursor>>,
-    camera: Query<(&Camera, &GlobalTransform)>,
-    input: Res<InputState>,
-) {
-    let Some(mouse) = input.mouse() else {
+fn cursor_system(mut cursor: Query<&mut Transform, With<Cursor>>, input: Res<InputState>) {
+    let Some(world_pos) = input.mouse_world() else {
         return;
     };
 
-    let camera = camera.single().unwrap();
-    let camera_transform = camera.1;
-    let camera = camera.0;
-    if let Ok(world_pos) = camera.viewport_to_world_2d(camera_transform, mouse) {
-        cursor.single_mut().unwrap().translation = world_pos.extend(0.0);
+    for mut transform in cursor.iter_mut() {
+        transform.translation = world_pos.extend(0.0);
     }
 }
@@ -0,0 +1,311 @@
+use std::collections::{BTreeSet, HashMap};
+
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::bookmarks::CameraBookmarks,
+    grab::Frozen,
+    joint::{Connection, JointKind, JointSnapshot, JointStrength},
+    modding::registry::Registry,
+    spawn::{ObjectSnapshot, SpawnShape, Spawnable, Spawned},
+};
+
+/// The version of the save format written by this build. Saves from older versions are read as
+/// far as they can be, and saves from newer ones are refused.
+pub const SAVE_VERSION: u32 = 1;
+
+/// A world as stored on disk.
+///
+/// Objects refer to what they were spawned as through `palette`, a list of registry paths, since
+/// ids are only given out for a single run of the game.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct WorldSave {
+    pub version: u32,
+    #[serde(default)]
+    pub palette: Vec<String>,
+    #[serde(default)]
+    pub objects: Vec<SavedObject>,
+    #[serde(default)]
+    pub joints: Vec<SavedJoint>,
+    #[serde(default)]
+    pub bookmarks: CameraBookmarks,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SavedBody {
+    Dynamic,
+    Static,
+    Kinematic,
+}
+
+impl From<RigidBody> for SavedBody {
+    fn from(body: RigidBody) -> Self {
+        match body {
+            RigidBody::Dynamic => SavedBody::Dynamic,
+            RigidBody::Static => SavedBody::Static,
+            RigidBody::Kinematic => SavedBody::Kinematic,
+        }
+    }
+}
+
+impl From<SavedBody> for RigidBody {
+    fn from(body: SavedBody) -> Self {
+        match body {
+            SavedBody::Dynamic => RigidBody::Dynamic,
+            SavedBody::Static => RigidBody::Static,
+            SavedBody::Kinematic => RigidBody::Kinematic,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedObject {
+    /// The index in the palette of what the object was spawned as
+    pub spawnable: usize,
+    pub position: Vec2,
+    /// The angle in radians, anticlockwise
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default = "default_scale")]
+    pub scale: Vec2,
+    pub body: SavedBody,
+    /// The kind of body it goes back to when let go, if frozen
+    #[serde(default)]
+    pub frozen: Option<SavedBody>,
+    #[serde(default)]
+    pub linear_velocity: Vec2,
+    #[serde(default)]
+    pub angular_velocity: f32,
+    /// The collider it had, which is kept even if the mod has since changed the spawnable's shape
+    pub shape: SpawnShape,
+    pub color: [f32; 4],
+    #[serde(default)]
+    pub image: Option<String>,
+    #[serde(default)]
+    pub size: Option<Vec2>,
+}
+
+fn default_scale() -> Vec2 {
+    Vec2::ONE
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedJoint {
+    pub kind: JointKind,
+    /// The indices of the objects joined, or nothing for an end attached to the world
+    pub body1: Option<usize>,
+    pub body2: Option<usize>,
+    pub anchor1: Vec2,
+    pub anchor2: Vec2,
+    #[serde(default)]
+    pub length: f32,
+    #[serde(default)]
+    pub axis: Vec2,
+    pub max_force: f32,
+    pub max_torque: f32,
+    /// Where the end attached to the world is
+    #[serde(default)]
+    pub world_anchor: Option<Vec2>,
+}
+
+impl WorldSave {
+    /// Records every spawned object and the joints between them, along with the camera bookmarks
+    pub fn capture(world: &mut World) -> Self {
+        let mut spawned = world.query_filtered::<Entity, With<Spawned>>();
+        let entities: Vec<Entity> = spawned.iter(world).collect();
+        let mut joints = world.query_filtered::<Entity, With<Connection>>();
+        let joints: Vec<Entity> = joints.iter(world).collect();
+
+        let registry = world.resource::<Registry<Spawnable>>();
+        let mut save = WorldSave {
+            version: SAVE_VERSION,
+            bookmarks: world.resource::<CameraBookmarks>().clone(),
+            ..default()
+        };
+        let mut palette = HashMap::new();
+        let mut indices = HashMap::new();
+
+        for entity in entities {
+            let Some(object) = ObjectSnapshot::capture(world, entity) else {
+                continue;
+            };
+            let id = object.spawned.0;
+            let (Some(path), Some(spawnable)) = (registry.resolve(id), registry.get(id)) else {
+                warn!("not saving {:?}, as its spawnable no longer exists", entity);
+                continue;
+            };
+            let index = *palette.entry(id).or_insert_with(|| {
+                save.palette.push(path.to_owned());
+                save.palette.len() - 1
+            });
+
+            indices.insert(entity, save.objects.len());
+            save.objects
+                .push(SavedObject::new(&object, index, spawnable.shape()));
+        }
+
+        for entity in joints {
+            let Some(joint) = JointSnapshot::capture(world, entity) else {
+                continue;
+            };
+            let connection = joint.connection;
+            let end = |body: Entity| match indices.get(&body) {
+                Some(&index) => Some(Some(index)),
+                None if Some(body) == connection.world_anchor => Some(None),
+                // Joined to something that wasn't saved
+                None => None,
+            };
+            let (Some(body1), Some(body2)) = (end(connection.body1), end(connection.body2)) else {
+                continue;
+            };
+            save.joints.push(SavedJoint {
+                kind: connection.kind,
+                body1,
+                body2,
+                anchor1: connection.anchor1,
+                anchor2: connection.anchor2,
+                length: connection.length,
+                axis: connection.axis,
+                max_force: joint.strength.max_force,
+                max_torque: joint.strength.max_torque,
+                world_anchor: joint.world_anchor,
+            });
+        }
+
+        save
+    }
+
+    /// Spawns the saved objects and joints into the world, returning how many objects were
+    /// spawned. Objects whose spawnables aren't registered are left out, with a warning for each
+    /// mod missing.
+    pub fn restore(&self, world: &mut World) -> usize {
+        let assets = world.resource::<AssetServer>().clone();
+        let mut entities: Vec<Option<Entity>> = vec![None; self.objects.len()];
+
+        world.resource_scope(|world, registry: Mut<Registry<Spawnable>>| {
+            let palette: Vec<_> = self
+                .palette
+                .iter()
+                .map(|path| registry.lookup(path))
+                .collect();
+
+            let missing: BTreeSet<&str> = self
+                .palette
+                .iter()
+                .zip(&palette)
+                .filter(|(_, id)| id.is_none())
+                .map(|(path, _)| path.split("::").next().unwrap_or(path))
+                .collect();
+            for namespace in missing {
+                warn!(
+                    "leaving out objects from \"{}\", which isn't loaded",
+                    namespace
+                );
+            }
+
+            let mut commands = world.commands();
+            for (object, entity) in self.objects.iter().zip(&mut entities) {
+                let Some(&Some(id)) = palette.get(object.spawnable) else {
+                    continue;
+                };
+                *entity =
+                    object
+                        .snapshot(Spawned(id), &assets)
+                        .spawn(&mut commands, &registry, &assets);
+                if let Some(entity) = *entity {
+                    commands.entity(entity).insert(object.shape.collider());
+                }
+            }
+        });
+
+        let mut commands = world.commands();
+        for joint in &self.joints {
+            let end = |body: Option<usize>| match body {
+                Some(index) => entities.get(index).copied().flatten(),
+                None => Some(Entity::PLACEHOLDER),
+            };
+            let (Some(body1), Some(body2)) = (end(joint.body1), end(joint.body2)) else {
+                continue;
+            };
+            if joint.world_anchor.is_none()
+                && (body1 == Entity::PLACEHOLDER || body2 == Entity::PLACEHOLDER)
+            {
+                warn!(
+                    "skipping a {} joint with no world anchor",
+                    joint.kind.name()
+                );
+                continue;
+            }
+
+            let snapshot = JointSnapshot {
+                connection: Connection {
+                    kind: joint.kind,
+                    body1,
+                    body2,
+                    anchor1: joint.anchor1,
+                    anchor2: joint.anchor2,
+                    length: joint.length,
+                    axis: joint.axis,
+                    world_anchor: joint.world_anchor.map(|_| Entity::PLACEHOLDER),
+                },
+                strength: JointStrength::new(joint.max_force, joint.max_torque),
+                world_anchor: joint.world_anchor,
+            };
+            snapshot.spawn(&mut commands, |body| body);
+        }
+        world.flush();
+
+        world.insert_resource(self.bookmarks.clone());
+        entities.iter().flatten().count()
+    }
+}
+
+impl SavedObject {
+    fn new(object: &ObjectSnapshot, spawnable: usize, shape: SpawnShape) -> Self {
+        let transform = object.transform;
+        Self {
+            spawnable,
+            position: transform.translation.truncate(),
+            rotation: transform.rotation.to_euler(EulerRot::ZYX).0,
+            scale: transform.scale.truncate(),
+            body: object.body.into(),
+            frozen: object.frozen.map(|frozen| frozen.body.into()),
+            linear_velocity: object.linear_velocity.0,
+            angular_velocity: object.angular_velocity.0,
+            shape,
+            color: object.sprite.color.to_srgba().to_f32_array(),
+            image: object.sprite.image.path().map(|path| path.to_string()),
+            size: object.sprite.custom_size,
+        }
+    }
+
+    fn snapshot(&self, spawned: Spawned, assets: &AssetServer) -> ObjectSnapshot {
+        let color = Color::srgba(self.color[0], self.color[1], self.color[2], self.color[3]);
+        let sprite = Sprite {
+            image: match &self.image {
+                Some(path) => assets.load(path.clone()),
+                None => default(),
+            },
+            color,
+            custom_size: Some(self.size.unwrap_or(self.shape.size())),
+            ..default()
+        };
+
+        ObjectSnapshot {
+            spawned,
+            transform: Transform {
+                translation: self.position.extend(0.0),
+                rotation: Quat::from_rotation_z(self.rotation),
+                scale: self.scale.extend(1.0),
+            },
+            body: self.body.into(),
+            frozen: self.frozen.map(|body| Frozen { body: body.into() }),
+            sprite,
+            linear_velocity: LinearVelocity(self.linear_velocity),
+            angular_velocity: AngularVelocity(self.angular_velocity),
+        }
+    }
+}
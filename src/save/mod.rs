@@ -0,0 +1,176 @@
+use std::{fs, io, path::PathBuf};
+
+use bevy::prelude::*;
+
+use crate::{
+    history::History,
+    input::{InputMapping, InputState},
+    joint::{Connection, despawn_connection},
+    modding::{
+        PostModLoad,
+        registry::{Id, Registry},
+    },
+    save::format::{SAVE_VERSION, WorldSave},
+    spawn::Spawned,
+};
+
+pub mod format;
+
+/// The file quick saves are written to
+pub const QUICK_SAVE_PATH: &str = "saves/quicksave.toml";
+
+/// Saves the world to disk and loads it back, when asked to with [`SaveWorld`] and [`LoadWorld`]
+/// or the quick save keys.
+///
+/// Bevy's scenes aren't used, as they store entities and registry ids, which only mean anything
+/// for a single run of the game. Saves are written as toml instead, like the game's other files,
+/// and refer to spawnables by registry path.
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<SaveWorld>()
+            .add_message::<LoadWorld>()
+            .add_systems(PostModLoad, init_save_input_ids)
+            .add_systems(Update, (quick_save, save_and_load).chain());
+    }
+}
+
+/// Asks for the world to be saved to a file
+#[derive(Debug, Clone, PartialEq, Message)]
+pub struct SaveWorld(pub PathBuf);
+
+/// Asks for the world to be replaced with the one saved in a file
+#[derive(Debug, Clone, PartialEq, Message)]
+pub struct LoadWorld(pub PathBuf);
+
+#[derive(Debug, Resource)]
+struct SaveInputIds {
+    quick_save: Id,
+    quick_load: Id,
+}
+
+fn init_save_input_ids(mut commands: Commands, inputs: Res<Registry<InputMapping>>) {
+    let ids = SaveInputIds {
+        quick_save: inputs.lookup("base::input::quick_save").unwrap(),
+        quick_load: inputs.lookup("base::input::quick_load").unwrap(),
+    };
+    commands.insert_resource(ids);
+}
+
+fn quick_save(
+    input: Res<InputState>,
+    ids: Res<SaveInputIds>,
+    mut saves: MessageWriter<SaveWorld>,
+    mut loads: MessageWriter<LoadWorld>,
+) {
+    if input.just_pressed(ids.quick_save) {
+        saves.write(SaveWorld(QUICK_SAVE_PATH.into()));
+    }
+    if input.just_pressed(ids.quick_load) {
+        loads.write(LoadWorld(QUICK_SAVE_PATH.into()));
+    }
+}
+
+fn save_and_load(
+    mut commands: Commands,
+    mut saves: MessageReader<SaveWorld>,
+    mut loads: MessageReader<LoadWorld>,
+) {
+    for SaveWorld(path) in saves.read() {
+        let path = path.clone();
+        commands.queue(move |world: &mut World| save_world(world, path));
+    }
+    for LoadWorld(path) in loads.read() {
+        let path = path.clone();
+        commands.queue(move |world: &mut World| load_world(world, path));
+    }
+}
+
+fn save_world(world: &mut World, path: PathBuf) {
+    let save = WorldSave::capture(world);
+
+    let contents = match toml::to_string_pretty(&save) {
+        Ok(c) => c,
+        Err(e) => {
+            error!("unable to serialise the world: {}", e);
+            return;
+        }
+    };
+
+    if let Some(parent) = path.parent()
+        && let Err(e) = fs::create_dir_all(parent)
+    {
+        error!("unable to create {}: {}", parent.display(), e);
+        return;
+    }
+
+    match fs::write(&path, contents) {
+        Ok(()) => info!(
+            "saved {} objects and {} joints to {}",
+            save.objects.len(),
+            save.joints.len(),
+            path.display()
+        ),
+        Err(e) => error!("unable to write {}: {}", path.display(), e),
+    }
+}
+
+/// Replaces the world with the one saved at `path`, leaving the world as it is if the save can't
+/// be read
+fn load_world(world: &mut World, path: PathBuf) {
+    let bytes = match fs::read(&path) {
+        Ok(b) => b,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            warn!("there is no save at {}", path.display());
+            return;
+        }
+        Err(e) => {
+            error!("unable to read {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    let save: WorldSave = match toml::from_slice(&bytes) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("error parsing {}:\n{}", path.display(), e);
+            return;
+        }
+    };
+    if save.version > SAVE_VERSION {
+        error!(
+            "{} was saved by a newer version of the game (version {}, this reads up to {})",
+            path.display(),
+            save.version,
+            SAVE_VERSION
+        );
+        return;
+    }
+
+    clear_world(world);
+    let spawned = save.restore(world);
+    info!("loaded {} objects from {}", spawned, path.display());
+}
+
+/// Removes every spawned object and joint, and forgets the edits made to them
+fn clear_world(world: &mut World) {
+    let mut joints = world.query::<(Entity, &Connection)>();
+    let joints: Vec<(Entity, Connection)> = joints
+        .iter(world)
+        .map(|(entity, connection)| (entity, *connection))
+        .collect();
+    let mut objects = world.query_filtered::<Entity, With<Spawned>>();
+    let objects: Vec<Entity> = objects.iter(world).collect();
+
+    let mut commands = world.commands();
+    for (entity, connection) in joints {
+        despawn_connection(&mut commands, entity, &connection);
+    }
+    for entity in objects {
+        commands.entity(entity).try_despawn();
+    }
+    world.flush();
+
+    world.resource_mut::<History>().clear();
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use avian2d::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    grab::Frozen,
+    history::{self, edits::ObjectsEdit},
+    input::{InputMapping, InputState},
+    joint::{Connection, JointStrength, spawn_joint},
+    modding::registry::{Id, Registry},
+    selection::Selected,
+    spawn::{ObjectSnapshot, Spawnable, Spawned},
+};
+
+/// How far a duplicate is placed from the original, in world units
+const DUPLICATE_OFFSET: Vec2 = Vec2::new(16.0, -16.0);
+
+/// The objects the player copied, along with the joints between them
+#[derive(Debug, Default, Resource)]
+pub struct Clipboard {
+    group: CopiedGroup,
+}
+
+#[derive(Debug, Default, Clone)]
+struct CopiedGroup {
+    /// The objects, relative to the middle of the group
+    objects: Vec<ObjectSnapshot>,
+    /// Joints between the copied objects, with the indices of the objects they connect
+    connections: Vec<(Connection, JointStrength, usize, usize)>,
+    /// The middle of the objects where they were copied from
+    centre: Vec2,
+}
+
+#[derive(Debug, Resource)]
+pub(super) struct ClipboardInputIds {
+    copy: Id,
+    paste: Id,
+    duplicate: Id,
+}
+
+pub(super) fn init_clipboard_input_ids(
+    mut commands: Commands,
+    inputs: Res<Registry<InputMapping>>,
+) {
+    let ids = ClipboardInputIds {
+        copy: inputs.lookup("base::input::copy").unwrap(),
+        paste: inputs.lookup("base::input::paste").unwrap(),
+        duplicate: inputs.lookup("base::input::duplicate").unwrap(),
+    };
+    commands.insert_resource(ids);
+}
+
+type SelectedObject<'a> = (
+    Entity,
+    &'a Spawned,
+    &'a Transform,
+    &'a RigidBody,
+    Option<&'a Frozen>,
+    &'a Sprite,
+);
+
+/// The selected objects and the joints they have
+#[derive(SystemParam)]
+pub(super) struct SelectedGroup<'w, 's> {
+    objects: Query<'w, 's, SelectedObject<'static>, With<Selected>>,
+    joints: Query<'w, 's, (&'static Connection, &'static JointStrength)>,
+}
+
+impl SelectedGroup<'_, '_> {
+    /// Copies the selected objects, and the joints between them. Objects not spawned from the
+    /// registry are left out, as there is no way to make more of them.
+    fn copy(&self) -> CopiedGroup {
+        let selected: Vec<SelectedObject> = self.objects.iter().collect();
+        if selected.is_empty() {
+            return CopiedGroup::default();
+        }
+        let centre = selected
+            .iter()
+            .map(|(_, _, transform, ..)| transform.translation.truncate())
+            .sum::<Vec2>()
+            / selected.len() as f32;
+
+        let indices: HashMap<Entity, usize> = selected
+            .iter()
+            .enumerate()
+            .map(|(index, (entity, ..))| (*entity, index))
+            .collect();
+        let objects = selected
+            .iter()
+            .map(|&(_, &spawned, transform, &body, frozen, sprite)| {
+                let mut transform = *transform;
+                transform.translation -= centre.extend(0.0);
+                ObjectSnapshot {
+                    spawned,
+                    transform,
+                    body,
+                    frozen: frozen.copied(),
+                    sprite: sprite.clone(),
+                    // Copies are pasted at rest
+                    linear_velocity: LinearVelocity::ZERO,
+                    angular_velocity: AngularVelocity::ZERO,
+                }
+            })
+            .collect();
+        let connections = self
+            .joints
+            .iter()
+            .filter_map(|(connection, strength)| {
+                let body1 = *indices.get(&connection.body1)?;
+                let body2 = *indices.get(&connection.body2)?;
+                Some((*connection, *strength, body1, body2))
+            })
+            .collect();
+
+        CopiedGroup {
+            objects,
+            connections,
+            centre,
+        }
+    }
+}
+
+/// Spawns a copied group centred on `position`, returning the new objects
+fn paste_group(
+    commands: &mut Commands,
+    registry: &Registry<Spawnable>,
+    assets: &AssetServer,
+    group: &CopiedGroup,
+    position: Vec2,
+) -> Vec<Entity> {
+    let entities: Vec<Option<Entity>> = group
+        .objects
+        .iter()
+        .map(|object| {
+            let mut object = object.clone();
+            object.transform.translation += position.extend(0.0);
+            object.spawn(commands, registry, assets)
+        })
+        .collect();
+
+    for &(connection, strength, index1, index2) in &group.connections {
+        if let (Some(body1), Some(body2)) = (entities[index1], entities[index2]) {
+            spawn_joint(commands, connection.with_bodies(body1, body2), strength);
+        }
+    }
+    entities.into_iter().flatten().collect()
+}
+
+/// Copies the selection, pastes it at the cursor, or duplicates it next to itself. Whatever is
+/// pasted becomes the selection.
+pub(super) fn copy_paste(
+    mut commands: Commands,
+    mut clipboard: ResMut<Clipboard>,
+    input: Res<InputState>,
+    ids: Res<ClipboardInputIds>,
+    registry: Res<Registry<Spawnable>>,
+    assets: Res<AssetServer>,
+    selection: SelectedGroup,
+) {
+    if input.just_pressed(ids.copy) {
+        clipboard.group = selection.copy();
+        debug!("copied {} objects", clipboard.group.objects.len());
+    }
+
+    let pasted = if input.just_pressed(ids.duplicate) {
+        let group = selection.copy();
+        let position = group.centre + DUPLICATE_OFFSET;
+        paste_group(&mut commands, &registry, &assets, &group, position)
+    } else if input.just_pressed(ids.paste)
+        && let Some(position) = input.mouse_world()
+    {
+        paste_group(
+            &mut commands,
+            &registry,
+            &assets,
+            &clipboard.group,
+            position,
+        )
+    } else {
+        return;
+    };
+
+    if pasted.is_empty() {
+        return;
+    }
+    history::record(&mut commands, ObjectsEdit::spawned(pasted.clone()));
+    for (entity, ..) in selection.objects.iter() {
+        commands.entity(entity).remove::<Selected>();
+    }
+    for entity in pasted {
+        commands.entity(entity).insert(Selected);
+    }
+}
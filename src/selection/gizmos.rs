@@ -0,0 +1,95 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    input::InputState,
+    selection::{GizmoMode, SelectDrag, SelectTool, Selected, selection_bounds},
+};
+
+/// The size of the gizmo's handles, and how close to one a click has to be to grab it, in world
+/// units
+const HANDLE_SIZE: f32 = 4.0;
+
+/// The length of the move gizmo's arrows, in world units
+const ARROW_LENGTH: f32 = 24.0;
+
+const SELECTED_COLOR: Color = Color::hsl(40.0, 1.0, 0.6);
+const GIZMO_COLOR: Color = Color::hsl(0.0, 0.0, 0.9);
+const BOX_COLOR: Color = Color::hsl(210.0, 1.0, 0.7);
+
+/// How far the rotate gizmo's ring is from the middle of the selection
+fn ring_radius(bounds: Rect) -> f32 {
+    bounds.half_size().length() + HANDLE_SIZE * 2.0
+}
+
+fn corners(bounds: Rect) -> [Vec2; 4] {
+    [
+        bounds.min,
+        Vec2::new(bounds.max.x, bounds.min.y),
+        bounds.max,
+        Vec2::new(bounds.min.x, bounds.max.y),
+    ]
+}
+
+/// Whether `point` is on the gizmo's handle for a selection covering `bounds`
+pub(super) fn on_handle(mode: GizmoMode, bounds: Rect, point: Vec2) -> bool {
+    let centre = bounds.center();
+    match mode {
+        GizmoMode::Move => (point - centre).abs().max_element() <= HANDLE_SIZE,
+        GizmoMode::Rotate => (point.distance(centre) - ring_radius(bounds)).abs() <= HANDLE_SIZE,
+        GizmoMode::Scale => corners(bounds)
+            .iter()
+            .any(|corner| corner.distance(point) <= HANDLE_SIZE),
+    }
+}
+
+/// Outlines everything selected
+pub(super) fn draw_selection(mut gizmos: Gizmos, selected: Query<&ColliderAabb, With<Selected>>) {
+    for aabb in selected.iter() {
+        let bounds = Rect::from_corners(aabb.min, aabb.max);
+        gizmos.rect_2d(bounds.center(), bounds.size(), SELECTED_COLOR);
+    }
+}
+
+/// Draws the gizmo for the selection, and the box being dragged out. This is the select tool's
+/// draw hook.
+pub fn draw_select_tool(
+    mut gizmos: Gizmos,
+    tool: Res<SelectTool>,
+    input: Res<InputState>,
+    selected: Query<&ColliderAabb, With<Selected>>,
+) {
+    if let (Some(SelectDrag::Box { start }), Some(point)) = (&tool.drag, input.mouse_world()) {
+        let area = Rect::from_corners(*start, point);
+        gizmos.rect_2d(area.center(), area.size(), BOX_COLOR);
+    }
+
+    let Some(bounds) = selection_bounds(selected.iter()) else {
+        return;
+    };
+    let centre = bounds.center();
+    match tool.mode {
+        GizmoMode::Move => {
+            gizmos.arrow_2d(
+                centre,
+                centre + Vec2::X * ARROW_LENGTH,
+                Color::hsl(0.0, 0.9, 0.6),
+            );
+            gizmos.arrow_2d(
+                centre,
+                centre + Vec2::Y * ARROW_LENGTH,
+                Color::hsl(120.0, 0.8, 0.5),
+            );
+            gizmos.rect_2d(centre, Vec2::splat(HANDLE_SIZE * 2.0), GIZMO_COLOR);
+        }
+        GizmoMode::Rotate => {
+            gizmos.circle_2d(centre, ring_radius(bounds), GIZMO_COLOR);
+        }
+        GizmoMode::Scale => {
+            gizmos.rect_2d(centre, bounds.size(), GIZMO_COLOR.with_alpha(0.4));
+            for corner in corners(bounds) {
+                gizmos.rect_2d(corner, Vec2::splat(HANDLE_SIZE * 2.0), GIZMO_COLOR);
+            }
+        }
+    }
+}
@@ -0,0 +1,364 @@
+use avian2d::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    Cursor,
+    history::{self, edits::TransformEdit},
+    input::{InputMapping, InputState},
+    modding::{
+        PostModLoad,
+        registry::{Id, Registry},
+    },
+    selection::{
+        clipboard::{Clipboard, copy_paste, init_clipboard_input_ids},
+        gizmos::{draw_selection, on_handle},
+    },
+    spawn::{Spawnable, Spawned, rebuild_collider},
+    tool::ToolSystems,
+};
+
+pub mod clipboard;
+pub mod gizmos;
+
+/// Lets the player select objects in the world with the select tool, then move, turn, resize and
+/// copy them.
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelectTool>()
+            .init_resource::<Clipboard>()
+            .add_systems(
+                PostModLoad,
+                (init_selection_input_ids, init_clipboard_input_ids),
+            )
+            .add_systems(
+                Update,
+                (copy_paste, draw_selection).chain().after(ToolSystems),
+            );
+    }
+}
+
+/// The smallest and largest an object can be resized to, as multiples of its original size
+pub const MIN_SCALE: f32 = 0.1;
+pub const MAX_SCALE: f32 = 16.0;
+
+/// Marks an entity as selected by the player
+#[derive(Debug, Default, Component)]
+pub struct Selected;
+
+/// What dragging the selection's gizmo does to it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoMode {
+    #[default]
+    Move,
+    Rotate,
+    Scale,
+}
+
+impl GizmoMode {
+    pub const ALL: [GizmoMode; 3] = [GizmoMode::Move, GizmoMode::Rotate, GizmoMode::Scale];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GizmoMode::Move => "Move",
+            GizmoMode::Rotate => "Rotate",
+            GizmoMode::Scale => "Scale",
+        }
+    }
+}
+
+/// The select tool's gizmo mode, and what the player is dragging with it
+#[derive(Debug, Default, Resource)]
+pub struct SelectTool {
+    pub mode: GizmoMode,
+    drag: Option<SelectDrag>,
+}
+
+#[derive(Debug, Clone)]
+enum SelectDrag {
+    /// Selecting everything in the box from `start` to the cursor
+    Box { start: Vec2 },
+    /// Moving, turning or resizing bodies about `centre`, from the transforms they had when the
+    /// drag started
+    Transform {
+        mode: GizmoMode,
+        start: Vec2,
+        centre: Vec2,
+        bodies: Vec<(Entity, Transform)>,
+    },
+}
+
+#[derive(Debug, Resource)]
+pub(crate) struct SelectionInputIds {
+    select: Id,
+    multi_select: Id,
+    cancel: Id,
+    tool_mode: Id,
+}
+
+fn init_selection_input_ids(mut commands: Commands, inputs: Res<Registry<InputMapping>>) {
+    let ids = SelectionInputIds {
+        select: inputs.lookup("base::input::select").unwrap(),
+        multi_select: inputs.lookup("base::input::multi_select").unwrap(),
+        cancel: inputs.lookup("base::input::cancel").unwrap(),
+        tool_mode: inputs.lookup("base::input::tool_mode").unwrap(),
+    };
+    commands.insert_resource(ids);
+}
+
+/// Finds what is under a point in the world, leaving out the cursor
+#[derive(SystemParam)]
+pub struct BodyPicker<'w, 's> {
+    spatial_query: SpatialQuery<'w, 's>,
+    cursor: Query<'w, 's, Entity, With<Cursor>>,
+}
+
+impl BodyPicker<'_, '_> {
+    fn filter(&self) -> SpatialQueryFilter {
+        SpatialQueryFilter::default().with_excluded_entities(self.cursor.iter())
+    }
+
+    /// Returns the colliders at `point`
+    pub fn at(&self, point: Vec2) -> Vec<Entity> {
+        self.spatial_query
+            .point_intersections(point, &self.filter())
+    }
+
+    /// Returns the colliders touching `area`
+    pub fn in_area(&self, area: Rect) -> Vec<Entity> {
+        if area.width() <= 0.0 || area.height() <= 0.0 {
+            return Vec::new();
+        }
+        let shape = Collider::rectangle(area.width(), area.height());
+        self.spatial_query
+            .shape_intersections(&shape, area.center(), 0.0, &self.filter())
+    }
+}
+
+/// The bodies the select tool picks and edits
+#[derive(SystemParam)]
+pub(crate) struct SelectBodies<'w, 's> {
+    picker: BodyPicker<'w, 's>,
+    transforms: Query<
+        'w,
+        's,
+        (
+            &'static mut Transform,
+            Option<&'static mut LinearVelocity>,
+            Option<&'static mut AngularVelocity>,
+        ),
+        With<RigidBody>,
+    >,
+    spawned: Query<'w, 's, &'static Spawned>,
+    registry: Res<'w, Registry<Spawnable>>,
+}
+
+impl SelectBodies<'_, '_> {
+    /// Returns the topmost body at `point`
+    fn at(&self, point: Vec2) -> Option<Entity> {
+        self.picker
+            .at(point)
+            .into_iter()
+            .find(|&entity| self.transforms.contains(entity))
+    }
+
+    /// Returns the bodies touching `area`
+    fn in_area(&self, area: Rect) -> Vec<Entity> {
+        self.picker
+            .in_area(area)
+            .into_iter()
+            .filter(|&entity| self.transforms.contains(entity))
+            .collect()
+    }
+}
+
+/// Returns the box around everything selected
+pub fn selection_bounds<'a>(aabbs: impl IntoIterator<Item = &'a ColliderAabb>) -> Option<Rect> {
+    aabbs
+        .into_iter()
+        .map(|aabb| Rect::from_corners(aabb.min, aabb.max))
+        .reduce(|a, b| a.union(b))
+}
+
+/// Selects objects by clicking on them or dragging a box around them, and moves, turns or resizes
+/// the selection by dragging it or its gizmo. This is the select tool's update hook.
+pub fn click_select(
+    mut commands: Commands,
+    mut tool: ResMut<SelectTool>,
+    input: Res<InputState>,
+    ids: Res<SelectionInputIds>,
+    mut bodies: SelectBodies,
+    selected: Query<(Entity, &ColliderAabb), With<Selected>>,
+) {
+    // The tool's mode steps through what the gizmo does
+    if input.just_pressed(ids.tool_mode) && tool.drag.is_none() {
+        let index = GizmoMode::ALL.iter().position(|&m| m == tool.mode);
+        tool.mode = GizmoMode::ALL[index.map_or(0, |i| (i + 1) % GizmoMode::ALL.len())];
+        info!("{} gizmo", tool.mode.name().to_lowercase());
+    }
+
+    // Cancelling puts dragged bodies back, or clears the selection when not dragging
+    if input.just_pressed(ids.cancel) {
+        match tool.drag.take() {
+            Some(SelectDrag::Transform { bodies: starts, .. }) => {
+                for (entity, from) in starts {
+                    if let Ok((mut transform, ..)) = bodies.transforms.get_mut(entity) {
+                        *transform = from;
+                    }
+                }
+            }
+            Some(SelectDrag::Box { .. }) => {}
+            None => {
+                for (entity, _) in selected.iter() {
+                    commands.entity(entity).remove::<Selected>();
+                }
+            }
+        }
+    }
+
+    let Some(point) = input.mouse_world() else {
+        return;
+    };
+
+    if input.just_pressed(ids.select) {
+        let selection: Vec<Entity> = selected.iter().map(|(entity, _)| entity).collect();
+        let bounds = selection_bounds(selected.iter().map(|(_, aabb)| aabb));
+        let multi = input.pressed(ids.multi_select);
+        let start_drag = |mode, centre, entities: &[Entity]| SelectDrag::Transform {
+            mode,
+            start: point,
+            centre,
+            bodies: entities
+                .iter()
+                .filter_map(|&entity| Some((entity, *bodies.transforms.get(entity).ok()?.0)))
+                .collect(),
+        };
+
+        if let Some(bounds) = bounds
+            && on_handle(tool.mode, bounds, point)
+        {
+            tool.drag = Some(start_drag(tool.mode, bounds.center(), &selection));
+            return;
+        }
+
+        let drag = match bodies.at(point) {
+            Some(entity) if multi => {
+                if selection.contains(&entity) {
+                    commands.entity(entity).remove::<Selected>();
+                } else {
+                    commands.entity(entity).insert(Selected);
+                }
+                None
+            }
+            // Dragging a selected object moves everything selected along with it
+            Some(entity) if selection.contains(&entity) => {
+                Some(start_drag(GizmoMode::Move, point, &selection))
+            }
+            Some(entity) => {
+                for &other in &selection {
+                    commands.entity(other).remove::<Selected>();
+                }
+                commands.entity(entity).insert(Selected);
+                Some(start_drag(GizmoMode::Move, point, &[entity]))
+            }
+            None => {
+                if !multi {
+                    for &other in &selection {
+                        commands.entity(other).remove::<Selected>();
+                    }
+                }
+                Some(SelectDrag::Box { start: point })
+            }
+        };
+        tool.drag = drag;
+    } else if input.pressed(ids.select) {
+        let Some(SelectDrag::Transform {
+            mode,
+            start,
+            centre,
+            bodies: starts,
+        }) = &tool.drag
+        else {
+            return;
+        };
+        for &(entity, from) in starts {
+            let Ok((mut transform, linear, angular)) = bodies.transforms.get_mut(entity) else {
+                continue;
+            };
+            *transform = drag_transform(*mode, from, *start, point, *centre);
+
+            // Held bodies shouldn't build up speed from gravity while being placed
+            if let Some(mut linear) = linear {
+                *linear = LinearVelocity::ZERO;
+            }
+            if let Some(mut angular) = angular {
+                *angular = AngularVelocity::ZERO;
+            }
+        }
+    } else {
+        match tool.drag.take() {
+            Some(SelectDrag::Box { start }) => {
+                for entity in bodies.in_area(Rect::from_corners(start, point)) {
+                    commands.entity(entity).insert(Selected);
+                }
+            }
+            Some(SelectDrag::Transform {
+                mode,
+                bodies: starts,
+                ..
+            }) => {
+                if mode == GizmoMode::Scale {
+                    for &(entity, _) in &starts {
+                        if let Ok(spawned) = bodies.spawned.get(entity) {
+                            rebuild_collider(&mut commands, &bodies.registry, entity, spawned);
+                        }
+                    }
+                }
+
+                let edit = TransformEdit::new(starts.into_iter().filter_map(|(entity, from)| {
+                    let (transform, ..) = bodies.transforms.get(entity).ok()?;
+                    Some((entity, from, *transform))
+                }));
+                if !edit.is_empty() {
+                    history::record(&mut commands, edit);
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+/// Forgets what was being dragged when switching away from the select tool
+pub fn end_select_drag(mut tool: ResMut<SelectTool>) {
+    tool.drag = None;
+}
+
+/// Where a body dragged from `start` to `point` ends up, given the transform it started with
+fn drag_transform(
+    mode: GizmoMode,
+    from: Transform,
+    start: Vec2,
+    point: Vec2,
+    centre: Vec2,
+) -> Transform {
+    let mut transform = from;
+    match mode {
+        GizmoMode::Move => {
+            transform.translation += (point - start).extend(0.0);
+        }
+        GizmoMode::Rotate => {
+            let angle = (start - centre).angle_to(point - centre);
+            transform.rotate_around(centre.extend(0.0), Quat::from_rotation_z(angle));
+        }
+        GizmoMode::Scale => {
+            let factor = point.distance(centre) / start.distance(centre).max(1.0);
+            let scale = (from.scale.truncate() * factor)
+                .clamp(Vec2::splat(MIN_SCALE), Vec2::splat(MAX_SCALE));
+            let offset = (from.translation.truncate() - centre) * factor;
+            transform.translation = (centre + offset).extend(from.translation.z);
+            transform.scale = scale.extend(from.scale.z);
+        }
+    }
+    transform
+}
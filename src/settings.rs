@@ -0,0 +1,472 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    input::{
+        Input, InputMap, InputMapping, InputState, InputType,
+        analog::{AnalogSettings, AxisDirection},
+        context::{InputContext, InputContexts},
+        is_modifier,
+    },
+    modding::{
+        PostModLoad,
+        registry::{Id, Registry},
+    },
+};
+
+/// Adds the settings screen, where the player can rebind their inputs.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RebindCapture>()
+            .add_systems(PostModLoad, init_settings_input_ids)
+            .add_systems(
+                Update,
+                (
+                    toggle_settings,
+                    (
+                        scroll_bindings,
+                        rebind_buttons,
+                        reset_buttons,
+                        capture_binding,
+                        sync_binding_slots,
+                        update_binding_rows,
+                    )
+                        .chain()
+                        .run_if(any_with_component::<SettingsScreen>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+const TEXT_COLOR: Color = Color::hsl(0.0, 0.0, 0.9);
+const CAPTURE_COLOR: Color = Color::hsl(50.0, 1.0, 0.6);
+const CONFLICT_COLOR: Color = Color::hsl(0.0, 0.8, 0.4);
+const BUTTON_COLOR: Color = Color::hsl(0.0, 0.0, 0.2);
+const HOVERED_BUTTON_COLOR: Color = Color::hsl(0.0, 0.0, 0.3);
+
+/// How far one line of the mouse wheel scrolls the list of bindings, in pixels
+const SCROLL_LINE_HEIGHT: f32 = 32.0;
+
+#[derive(Debug, Resource)]
+struct SettingsInputIds {
+    settings: Id,
+    back: Id,
+    menu: Id,
+}
+
+fn init_settings_input_ids(
+    mut commands: Commands,
+    inputs: Res<Registry<InputMapping>>,
+    contexts: Res<Registry<InputContext>>,
+) {
+    let ids = SettingsInputIds {
+        settings: inputs.lookup("base::input::settings").unwrap(),
+        back: inputs.lookup("base::input::back").unwrap(),
+        menu: contexts.lookup("base::context::menu").unwrap(),
+    };
+    commands.insert_resource(ids);
+}
+
+/// The root node of the settings screen
+#[derive(Debug, Component)]
+pub struct SettingsScreen;
+
+/// The fewest binding slots shown for each id, so there are always primary and secondary slots
+const SLOTS: usize = 2;
+
+/// The scrolling list of bindings
+#[derive(Debug, Component)]
+struct BindingList;
+
+/// Holds the buttons for an id's binding slots, with one for each binding and an empty one to
+/// add another
+#[derive(Debug, Component)]
+struct BindingSlots(Id);
+
+/// The binding slot currently waiting for the player to press its new binding
+#[derive(Debug, Default, Resource)]
+struct RebindCapture {
+    target: Option<(Id, usize)>,
+    /// Whether the click that started the capture has passed, so it isn't recorded as the binding
+    armed: bool,
+}
+
+impl RebindCapture {
+    fn start(&mut self, id: Id, slot: usize) {
+        self.target = Some((id, slot));
+        self.armed = false;
+    }
+
+    fn stop(&mut self) {
+        self.target = None;
+    }
+}
+
+/// The button showing a binding slot for an id, which starts capturing a new binding when pressed
+#[derive(Debug, Component)]
+struct RebindButton {
+    id: Id,
+    slot: usize,
+}
+
+/// Resets the binding for an id to its default
+#[derive(Debug, Component)]
+struct ResetButton(Id);
+
+fn toggle_settings(
+    mut commands: Commands,
+    screen: Query<Entity, With<SettingsScreen>>,
+    mut capture: ResMut<RebindCapture>,
+    mut contexts: ResMut<InputContexts>,
+    input: Res<InputState>,
+    ids: Res<SettingsInputIds>,
+    registry: Res<Registry<InputMapping>>,
+) {
+    // Keys pressed while capturing belong to the new binding
+    if capture.target.is_some() {
+        return;
+    }
+
+    match screen.single() {
+        Ok(entity) => {
+            if input.just_pressed(ids.settings) || input.just_pressed(ids.back) {
+                commands.entity(entity).despawn();
+                capture.stop();
+                contexts.remove(ids.menu);
+            }
+        }
+        Err(_) => {
+            if input.just_pressed(ids.settings) {
+                spawn_settings_screen(&mut commands, &registry);
+                contexts.push(ids.menu);
+            }
+        }
+    }
+}
+
+fn spawn_settings_screen(commands: &mut Commands, registry: &Registry<InputMapping>) {
+    let mut mappings: Vec<(Id, String)> = registry
+        .iter()
+        .map(|(&id, mapping)| (id, display_name(registry, id, mapping)))
+        .collect();
+    mappings.sort_by(|a, b| a.1.cmp(&b.1));
+
+    let screen = commands
+        .spawn((
+            SettingsScreen,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::hsla(0.0, 0.0, 0.0, 0.6)),
+            GlobalZIndex(i32::MAX),
+        ))
+        .id();
+
+    let panel = commands
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(16.0)),
+                max_height: Val::Percent(90.0),
+                ..default()
+            },
+            BackgroundColor(Color::hsl(0.0, 0.0, 0.1)),
+            ChildOf(screen),
+        ))
+        .id();
+
+    commands.spawn((
+        Text::new("Key Bindings"),
+        TextFont::from_font_size(24.0),
+        TextColor(TEXT_COLOR),
+        Node {
+            margin: UiRect::bottom(Val::Px(8.0)),
+            ..default()
+        },
+        ChildOf(panel),
+    ));
+
+    commands.spawn((
+        row_node(),
+        ChildOf(panel),
+        children![
+            label_node("Action", Val::Px(160.0)),
+            label_node("Bindings", Val::Auto),
+        ],
+    ));
+
+    let list = commands
+        .spawn((
+            BindingList,
+            Node {
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                // Lets the list shrink to fit the panel rather than overflowing it
+                min_height: Val::Px(0.0),
+                overflow: Overflow::scroll_y(),
+                ..default()
+            },
+            ChildOf(panel),
+        ))
+        .id();
+
+    for (id, name) in mappings {
+        let row = commands
+            .spawn((
+                row_node(),
+                ChildOf(list),
+                children![
+                    label_node(&name, Val::Px(160.0)),
+                    (BindingSlots(id), row_node()),
+                ],
+            ))
+            .id();
+
+        commands.spawn((
+            ResetButton(id),
+            button_node(Val::Auto),
+            ChildOf(row),
+            children![(Text::new("Reset"), TextColor(TEXT_COLOR))],
+        ));
+    }
+}
+
+fn row_node() -> Node {
+    Node {
+        column_gap: Val::Px(8.0),
+        align_items: AlignItems::Center,
+        ..default()
+    }
+}
+
+fn label_node(text: &str, width: Val) -> (Text, TextColor, Node) {
+    (
+        Text::new(text),
+        TextColor(TEXT_COLOR),
+        Node { width, ..default() },
+    )
+}
+
+fn button_node(width: Val) -> impl Bundle {
+    (
+        Button,
+        Node {
+            width,
+            padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+            border: UiRect::all(Val::Px(2.0)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(BUTTON_COLOR),
+        BorderColor::all(BUTTON_COLOR),
+    )
+}
+
+/// The name shown for a mapping, falling back to its registry path if it has no name
+fn display_name(registry: &Registry<InputMapping>, id: Id, mapping: &InputMapping) -> String {
+    if !mapping.name().is_empty() {
+        return mapping.name().to_string();
+    }
+    registry.resolve(id).unwrap_or_default().to_string()
+}
+
+fn scroll_bindings(
+    input: Res<InputState>,
+    mut lists: Query<(&mut ScrollPosition, &ComputedNode), With<BindingList>>,
+) {
+    let scroll = input.scroll();
+    if scroll == 0.0 {
+        return;
+    }
+    for (mut position, node) in lists.iter_mut() {
+        // The computed sizes are in physical pixels, while the scroll position is logical
+        let max = (node.content_size().y - node.size().y).max(0.0) * node.inverse_scale_factor();
+        position.y = (position.y - scroll * SCROLL_LINE_HEIGHT).clamp(0.0, max);
+    }
+}
+
+fn rebind_buttons(
+    buttons: Query<(&Interaction, &RebindButton), Changed<Interaction>>,
+    mut capture: ResMut<RebindCapture>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction == Interaction::Pressed {
+            capture.start(button.id, button.slot);
+        }
+    }
+}
+
+fn reset_buttons(
+    buttons: Query<(&Interaction, &ResetButton), Changed<Interaction>>,
+    mut map: ResMut<InputMap>,
+    mut capture: ResMut<RebindCapture>,
+    registry: Res<Registry<InputMapping>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        if let Some(mapping) = registry.get(button.0) {
+            map.insert(button.0, mapping.defaults().to_vec());
+        }
+        capture.stop();
+    }
+}
+
+fn capture_binding(
+    mut capture: ResMut<RebindCapture>,
+    mut map: ResMut<InputMap>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    analog: Res<AnalogSettings>,
+) {
+    let Some((id, slot)) = capture.target else {
+        return;
+    };
+
+    if !capture.armed {
+        capture.armed = true;
+        return;
+    }
+
+    let modifiers: HashSet<KeyCode> = keys
+        .get_pressed()
+        .copied()
+        .filter(|&k| is_modifier(k))
+        .collect();
+
+    let input_type = if let Some(&key) = keys.get_just_pressed().find(|&&k| !is_modifier(k)) {
+        match key {
+            KeyCode::Escape => {
+                capture.stop();
+                return;
+            }
+            KeyCode::Backspace => InputType::None,
+            key => InputType::KeyButton(key),
+        }
+    } else if let Some(&button) = mouse_buttons.get_just_pressed().next() {
+        InputType::MouseButton(button)
+    } else if let Some(&button) = gamepads.iter().find_map(|g| g.get_just_pressed().next()) {
+        InputType::GamepadButton(button)
+    } else if let Some((axis, direction)) = gamepads.iter().find_map(|g| pushed_axis(g, &analog)) {
+        InputType::GamepadAxis(axis, direction)
+    } else if let Some(&key) = keys.get_just_released().find(|&&k| is_modifier(k)) {
+        // A modifier released without pressing anything else is bound on its own
+        InputType::KeyButton(key)
+    } else {
+        return;
+    };
+
+    let input = match input_type {
+        InputType::None => Input::none(),
+        input_type => Input::new(input_type, modifiers),
+    };
+    map.set_slot(id, slot, input);
+    capture.stop();
+}
+
+/// Returns the first stick or axis pushed far enough to count as pressed
+fn pushed_axis(gamepad: &Gamepad, analog: &AnalogSettings) -> Option<(GamepadAxis, AxisDirection)> {
+    GamepadAxis::all().into_iter().find_map(|axis| {
+        let value = gamepad.get(axis)?;
+        if value >= analog.press_threshold {
+            Some((axis, AxisDirection::Positive))
+        } else if value <= -analog.press_threshold {
+            Some((axis, AxisDirection::Negative))
+        } else {
+            None
+        }
+    })
+}
+
+/// Keeps a button for each of an id's bindings, with an empty one after them to add another
+fn sync_binding_slots(
+    mut commands: Commands,
+    rows: Query<(Entity, &BindingSlots, Option<&Children>)>,
+    buttons: Query<&RebindButton>,
+    map: Res<InputMap>,
+) {
+    for (entity, &BindingSlots(id), children) in rows.iter() {
+        let wanted = (map.get(id).map_or(0, <[Input]>::len) + 1).max(SLOTS);
+        let existing: Vec<(Entity, usize)> = children
+            .iter()
+            .flat_map(|children| children.iter())
+            .filter_map(|&child| Some((child, buttons.get(child).ok()?.slot)))
+            .collect();
+
+        for &(button, slot) in &existing {
+            if slot >= wanted {
+                commands.entity(button).despawn();
+            }
+        }
+        for slot in existing.len()..wanted {
+            commands.spawn((
+                RebindButton { id, slot },
+                button_node(Val::Px(200.0)),
+                ChildOf(entity),
+                children![(Text::default(), TextColor(TEXT_COLOR))],
+            ));
+        }
+    }
+}
+
+fn update_binding_rows(
+    mut buttons: Query<(
+        &RebindButton,
+        &Interaction,
+        &Children,
+        &mut BackgroundColor,
+        &mut BorderColor,
+    )>,
+    mut texts: Query<(&mut Text, &mut TextColor)>,
+    map: Res<InputMap>,
+    capture: Res<RebindCapture>,
+    registry: Res<Registry<InputMapping>>,
+) {
+    let conflicts = map.conflicts(&registry);
+
+    for (button, interaction, children, mut background, mut border) in buttons.iter_mut() {
+        let target = (button.id, button.slot);
+
+        let (label, color) = if capture.target == Some(target) {
+            ("Press a key...".to_string(), CAPTURE_COLOR)
+        } else {
+            let label = map
+                .get_slot(button.id, button.slot)
+                .map(Input::to_string)
+                .unwrap_or_else(|| InputType::None.to_string());
+            (label, TEXT_COLOR)
+        };
+
+        let border_color = if conflicts.contains(&target) {
+            CONFLICT_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+        border.set_if_neq(BorderColor::all(border_color));
+
+        let background_color = match interaction {
+            Interaction::None => BUTTON_COLOR,
+            _ => HOVERED_BUTTON_COLOR,
+        };
+        background.set_if_neq(BackgroundColor(background_color));
+
+        for &child in children.iter() {
+            if let Ok((mut text, mut text_color)) = texts.get_mut(child) {
+                text.set_if_neq(Text::new(label.clone()));
+                text_color.set_if_neq(TextColor(color));
+            }
+        }
+    }
+}
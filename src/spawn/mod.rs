@@ -0,0 +1,373 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    grab::Frozen,
+    history::{self, edits::ObjectsEdit},
+    input::{InputMapping, InputState},
+    modding::{
+        PostModLoad,
+        registry::{Id, Registry},
+    },
+    spawn::palette::{PaletteScreen, SpawnPalette, palette_buttons, update_palette_buttons},
+    tool::{ActiveTool, DEFAULT_TOOL, Tool},
+};
+
+pub mod palette;
+
+/// Adds the objects mods can register, and the palette the player spawns them from with the spawn
+/// tool.
+pub struct SpawnPlugin;
+
+impl Plugin for SpawnPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Registry::<Spawnable>::new())
+            .init_resource::<SpawnPalette>()
+            .add_systems(PostModLoad, init_spawn_input_ids)
+            .add_systems(
+                Update,
+                (
+                    toggle_spawn_tool,
+                    (palette_buttons, update_palette_buttons)
+                        .chain()
+                        .run_if(any_with_component::<PaletteScreen>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// An object the player can spawn into the world
+#[derive(Debug, Clone)]
+pub struct Spawnable {
+    name: String,
+    category: String,
+    shape: SpawnShape,
+    body: RigidBody,
+    color: Color,
+    /// The image drawn over the shape, as an asset path
+    image: Option<String>,
+    /// The total mass, or none to work it out from the collider's size
+    mass: Option<f32>,
+    friction: f32,
+    restitution: f32,
+}
+
+impl Spawnable {
+    pub fn new(name: &str, shape: SpawnShape) -> Self {
+        Self {
+            name: name.to_string(),
+            category: String::new(),
+            shape,
+            body: RigidBody::Dynamic,
+            color: Color::WHITE,
+            image: None,
+            mass: None,
+            friction: 0.5,
+            restitution: 0.0,
+        }
+    }
+
+    pub fn in_category(mut self, category: &str) -> Self {
+        self.category = category.to_string();
+        self
+    }
+
+    pub fn with_body(mut self, body: RigidBody) -> Self {
+        self.body = body;
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_image(mut self, path: &str) -> Self {
+        self.image = Some(path.to_string());
+        self
+    }
+
+    pub fn with_mass(mut self, mass: f32) -> Self {
+        self.mass = Some(mass);
+        self
+    }
+
+    pub fn with_friction(mut self, friction: f32) -> Self {
+        self.friction = friction;
+        self
+    }
+
+    pub fn with_restitution(mut self, restitution: f32) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn category(&self) -> &str {
+        &self.category
+    }
+
+    pub fn shape(&self) -> SpawnShape {
+        self.shape
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Spawns the object centred on `position`
+    pub fn spawn(
+        &self,
+        commands: &mut Commands,
+        assets: &AssetServer,
+        id: Id,
+        position: Vec2,
+    ) -> Entity {
+        let size = self.shape.size();
+        let sprite = match &self.image {
+            Some(path) => Sprite {
+                image: assets.load(path),
+                color: self.color,
+                custom_size: Some(size),
+                ..default()
+            },
+            None => Sprite::from_color(self.color, size),
+        };
+
+        let mut entity = commands.spawn((
+            Spawned(id),
+            Transform::from_translation(position.extend(0.0)),
+            self.body,
+            self.shape.collider(),
+            Friction::new(self.friction),
+            Restitution::new(self.restitution),
+            sprite,
+        ));
+        if let Some(mass) = self.mass {
+            entity.insert(Mass(mass));
+        }
+        entity.id()
+    }
+}
+
+/// The outline of a spawnable object, in world units
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SpawnShape {
+    Rectangle {
+        width: f32,
+        height: f32,
+    },
+    Circle {
+        radius: f32,
+    },
+    /// A rectangle `length` tall with half circles of `radius` on each end
+    Capsule {
+        radius: f32,
+        length: f32,
+    },
+}
+
+impl SpawnShape {
+    pub fn collider(&self) -> Collider {
+        match *self {
+            SpawnShape::Rectangle { width, height } => Collider::rectangle(width, height),
+            SpawnShape::Circle { radius } => Collider::circle(radius),
+            SpawnShape::Capsule { radius, length } => Collider::capsule(radius, length),
+        }
+    }
+
+    /// The size of the box around the shape
+    pub fn size(&self) -> Vec2 {
+        match *self {
+            SpawnShape::Rectangle { width, height } => Vec2::new(width, height),
+            SpawnShape::Circle { radius } => Vec2::splat(radius * 2.0),
+            SpawnShape::Capsule { radius, length } => {
+                Vec2::new(radius * 2.0, length + radius * 2.0)
+            }
+        }
+    }
+
+    fn draw_outline(&self, gizmos: &mut Gizmos, position: Vec2, color: Color) {
+        match *self {
+            SpawnShape::Rectangle { width, height } => {
+                gizmos.rect_2d(position, Vec2::new(width, height), color);
+            }
+            SpawnShape::Circle { radius } => {
+                gizmos.circle_2d(position, radius, color);
+            }
+            SpawnShape::Capsule { radius, length } => {
+                gizmos.primitive_2d(&Capsule2d::new(radius, length), position, color);
+            }
+        }
+    }
+}
+
+/// Marks an object spawned from the registry, with the id of what it was spawned as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct Spawned(pub Id);
+
+/// A spawned object as it was at one moment, which can be spawned again
+#[derive(Debug, Clone)]
+pub struct ObjectSnapshot {
+    pub spawned: Spawned,
+    pub transform: Transform,
+    pub body: RigidBody,
+    pub frozen: Option<Frozen>,
+    pub sprite: Sprite,
+    pub linear_velocity: LinearVelocity,
+    pub angular_velocity: AngularVelocity,
+}
+
+impl ObjectSnapshot {
+    /// Returns how `entity` is now, if it was spawned from the registry
+    pub fn capture(world: &World, entity: Entity) -> Option<Self> {
+        let entity = world.get_entity(entity).ok()?;
+        Some(Self {
+            spawned: *entity.get::<Spawned>()?,
+            transform: *entity.get::<Transform>()?,
+            body: *entity.get::<RigidBody>()?,
+            frozen: entity.get::<Frozen>().copied(),
+            sprite: entity.get::<Sprite>()?.clone(),
+            linear_velocity: entity.get().copied().unwrap_or_default(),
+            angular_velocity: entity.get().copied().unwrap_or_default(),
+        })
+    }
+
+    /// Spawns the object as it was, or nothing if what it was spawned as no longer exists
+    pub fn spawn(
+        &self,
+        commands: &mut Commands,
+        registry: &Registry<Spawnable>,
+        assets: &AssetServer,
+    ) -> Option<Entity> {
+        let Some(spawnable) = registry.get(self.spawned.0) else {
+            warn!("can't spawn an object whose spawnable no longer exists");
+            return None;
+        };
+
+        let position = self.transform.translation.truncate();
+        let entity = spawnable.spawn(commands, assets, self.spawned.0, position);
+        let mut entity = commands.entity(entity);
+        entity.insert((
+            self.transform,
+            self.body,
+            self.sprite.clone(),
+            self.linear_velocity,
+            self.angular_velocity,
+        ));
+        if let Some(frozen) = self.frozen {
+            entity.insert(frozen);
+        }
+        Some(entity.id())
+    }
+}
+
+/// Gives a spawned object a fresh collider from its shape, so its mass is worked out again after
+/// being resized
+pub fn rebuild_collider(
+    commands: &mut Commands,
+    registry: &Registry<Spawnable>,
+    entity: Entity,
+    spawned: &Spawned,
+) {
+    match registry.get(spawned.0) {
+        Some(spawnable) => {
+            commands.entity(entity).insert(spawnable.shape().collider());
+        }
+        None => warn!(
+            "{:?} was spawned as a spawnable that no longer exists",
+            entity
+        ),
+    }
+}
+
+#[derive(Debug, Resource)]
+pub(crate) struct SpawnInputIds {
+    select: Id,
+    cancel: Id,
+    spawn_menu: Id,
+    spawn_tool: Id,
+    default_tool: Id,
+}
+
+fn init_spawn_input_ids(
+    mut commands: Commands,
+    inputs: Res<Registry<InputMapping>>,
+    tools: Res<Registry<Tool>>,
+) {
+    let ids = SpawnInputIds {
+        select: inputs.lookup("base::input::select").unwrap(),
+        cancel: inputs.lookup("base::input::cancel").unwrap(),
+        spawn_menu: inputs.lookup("base::input::spawn_menu").unwrap(),
+        spawn_tool: tools.lookup("base::tool::spawn").unwrap(),
+        default_tool: tools.lookup(DEFAULT_TOOL).unwrap(),
+    };
+    commands.insert_resource(ids);
+}
+
+/// Switches to the spawn tool, or back to the default tool if it is already in use
+fn toggle_spawn_tool(
+    mut active: ResMut<ActiveTool>,
+    input: Res<InputState>,
+    ids: Res<SpawnInputIds>,
+) {
+    if !input.just_pressed(ids.spawn_menu) {
+        return;
+    }
+
+    if active.is(ids.spawn_tool) {
+        active.set(ids.default_tool);
+    } else {
+        active.set(ids.spawn_tool);
+    }
+}
+
+/// Places the object picked from the palette at the cursor. This is the spawn tool's update hook.
+pub fn place_spawnable(
+    mut commands: Commands,
+    mut palette: ResMut<SpawnPalette>,
+    input: Res<InputState>,
+    ids: Res<SpawnInputIds>,
+    registry: Res<Registry<Spawnable>>,
+    assets: Res<AssetServer>,
+) {
+    if input.just_pressed(ids.cancel) {
+        palette.selected = None;
+    }
+    if !input.just_pressed(ids.select) {
+        return;
+    }
+    let (Some(id), Some(position)) = (palette.selected, input.mouse_world()) else {
+        return;
+    };
+    let Some(spawnable) = registry.get(id) else {
+        return;
+    };
+
+    let entity = spawnable.spawn(&mut commands, &assets, id, position);
+    history::record(&mut commands, ObjectsEdit::spawned(vec![entity]));
+}
+
+/// Outlines where the picked object will be placed. This is the spawn tool's draw hook.
+pub fn preview_placement(
+    mut gizmos: Gizmos,
+    palette: Res<SpawnPalette>,
+    input: Res<InputState>,
+    registry: Res<Registry<Spawnable>>,
+) {
+    let (Some(id), Some(position)) = (palette.selected, input.mouse_world()) else {
+        return;
+    };
+    let Some(spawnable) = registry.get(id) else {
+        return;
+    };
+
+    let color = spawnable.color().with_alpha(0.6);
+    spawnable.shape().draw_outline(&mut gizmos, position, color);
+}
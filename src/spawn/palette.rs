@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    modding::registry::{Id, Registry},
+    spawn::Spawnable,
+};
+
+const TEXT_COLOR: Color = Color::hsl(0.0, 0.0, 0.9);
+const HEADING_COLOR: Color = Color::hsl(0.0, 0.0, 0.6);
+const BUTTON_COLOR: Color = Color::hsl(0.0, 0.0, 0.2);
+const HOVERED_BUTTON_COLOR: Color = Color::hsl(0.0, 0.0, 0.3);
+const SELECTED_BUTTON_COLOR: Color = Color::hsl(210.0, 0.6, 0.35);
+
+/// The object picked from the palette, which clicking in the world places
+#[derive(Debug, Default, Resource)]
+pub struct SpawnPalette {
+    pub selected: Option<Id>,
+}
+
+/// The root node of the spawn palette
+#[derive(Debug, Component)]
+pub struct PaletteScreen;
+
+/// Picks a spawnable to place when pressed
+#[derive(Debug, Component)]
+pub(super) struct PaletteButton(Id);
+
+/// Shows the palette. This is the spawn tool's activate hook.
+pub fn open_palette(mut commands: Commands, registry: Res<Registry<Spawnable>>) {
+    spawn_palette(&mut commands, &registry);
+}
+
+/// Hides the palette. This is the spawn tool's deactivate hook.
+pub fn close_palette(mut commands: Commands, screen: Query<Entity, With<PaletteScreen>>) {
+    for entity in screen.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn spawn_palette(commands: &mut Commands, registry: &Registry<Spawnable>) {
+    // Group by category, with both the categories and their objects in alphabetical order
+    let mut categories: BTreeMap<&str, Vec<(Id, &Spawnable)>> = BTreeMap::new();
+    for (&id, spawnable) in registry.iter() {
+        categories
+            .entry(spawnable.category())
+            .or_default()
+            .push((id, spawnable));
+    }
+
+    let screen = commands
+        .spawn((
+            PaletteScreen,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(8.0),
+                top: Val::Px(8.0),
+                max_height: Val::Percent(90.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                overflow: Overflow::scroll_y(),
+                ..default()
+            },
+            BackgroundColor(Color::hsla(0.0, 0.0, 0.1, 0.9)),
+        ))
+        .id();
+
+    for (category, mut spawnables) in categories {
+        spawnables.sort_by(|a, b| a.1.name().cmp(b.1.name()));
+
+        let heading = if category.is_empty() {
+            "Other"
+        } else {
+            category
+        };
+        commands.spawn((
+            Text::new(heading),
+            TextFont::from_font_size(14.0),
+            TextColor(HEADING_COLOR),
+            Node {
+                margin: UiRect::top(Val::Px(4.0)),
+                ..default()
+            },
+            ChildOf(screen),
+        ));
+
+        for (id, spawnable) in spawnables {
+            commands.spawn((
+                PaletteButton(id),
+                Button,
+                Node {
+                    width: Val::Px(160.0),
+                    column_gap: Val::Px(8.0),
+                    padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                BackgroundColor(BUTTON_COLOR),
+                ChildOf(screen),
+                children![
+                    (
+                        Node {
+                            width: Val::Px(12.0),
+                            height: Val::Px(12.0),
+                            ..default()
+                        },
+                        BackgroundColor(spawnable.color()),
+                    ),
+                    (Text::new(spawnable.name()), TextColor(TEXT_COLOR)),
+                ],
+            ));
+        }
+    }
+}
+
+/// Picks the pressed object, or puts it back if it was already picked
+pub(super) fn palette_buttons(
+    buttons: Query<(&Interaction, &PaletteButton), Changed<Interaction>>,
+    mut palette: ResMut<SpawnPalette>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        palette.selected = match palette.selected {
+            Some(id) if id == button.0 => None,
+            _ => Some(button.0),
+        };
+    }
+}
+
+pub(super) fn update_palette_buttons(
+    mut buttons: Query<(&PaletteButton, &Interaction, &mut BackgroundColor)>,
+    palette: Res<SpawnPalette>,
+) {
+    for (button, interaction, mut background) in buttons.iter_mut() {
+        let color = if palette.selected == Some(button.0) {
+            SELECTED_BUTTON_COLOR
+        } else if *interaction != Interaction::None {
+            HOVERED_BUTTON_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+        background.set_if_neq(BackgroundColor(color));
+    }
+}
@@ -0,0 +1,227 @@
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+use crate::{
+    grab::{Frozen, freeze, thaw},
+    history::{
+        self,
+        edits::{FreezeEdit, ObjectsEdit, PaintEdit, TransformEdit},
+    },
+    input::{InputMapping, InputState},
+    modding::{
+        PostModLoad,
+        registry::{Id, Registry},
+    },
+    selection::{BodyPicker, MAX_SCALE, MIN_SCALE},
+    spawn::{Spawnable, Spawned, rebuild_collider},
+};
+
+/// Adds the state used by the tools that edit objects directly.
+pub struct BuiltinToolsPlugin;
+
+impl Plugin for BuiltinToolsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ResizeState>()
+            .init_resource::<PaintState>()
+            .add_systems(PostModLoad, init_builtin_tool_input_ids);
+    }
+}
+
+/// The colours the paint tool steps through
+const PAINT_COLORS: [Color; 8] = [
+    Color::hsl(0.0, 1.0, 0.5),
+    Color::hsl(30.0, 1.0, 0.5),
+    Color::hsl(60.0, 1.0, 0.5),
+    Color::hsl(120.0, 0.8, 0.45),
+    Color::hsl(210.0, 1.0, 0.5),
+    Color::hsl(280.0, 0.8, 0.6),
+    Color::hsl(0.0, 0.0, 0.95),
+    Color::hsl(0.0, 0.0, 0.15),
+];
+
+/// The object being resized, with its scale and the cursor's distance from its centre when the
+/// resize started
+#[derive(Debug, Default, Resource)]
+pub struct ResizeState {
+    target: Option<(Entity, Vec3, f32)>,
+}
+
+/// The colour the paint tool paints with, as an index into its colours
+#[derive(Debug, Default, Resource)]
+pub struct PaintState {
+    color: usize,
+}
+
+impl PaintState {
+    pub fn color(&self) -> Color {
+        PAINT_COLORS[self.color % PAINT_COLORS.len()]
+    }
+}
+
+#[derive(Debug, Resource)]
+pub(crate) struct BuiltinToolInputIds {
+    select: Id,
+    tool_mode: Id,
+}
+
+fn init_builtin_tool_input_ids(mut commands: Commands, inputs: Res<Registry<InputMapping>>) {
+    let ids = BuiltinToolInputIds {
+        select: inputs.lookup("base::input::select").unwrap(),
+        tool_mode: inputs.lookup("base::input::tool_mode").unwrap(),
+    };
+    commands.insert_resource(ids);
+}
+
+/// Returns the topmost body under the cursor that `bodies` accepts
+fn body_at_cursor(
+    input: &InputState,
+    picker: &BodyPicker,
+    bodies: impl Fn(Entity) -> bool,
+) -> Option<Entity> {
+    let point = input.mouse_world()?;
+    picker.at(point).into_iter().find(|&entity| bodies(entity))
+}
+
+/// Despawns the clicked object. This is the delete tool's update hook.
+pub fn delete_tool(
+    mut commands: Commands,
+    input: Res<InputState>,
+    ids: Res<BuiltinToolInputIds>,
+    picker: BodyPicker,
+    bodies: Query<(), With<RigidBody>>,
+) {
+    if !input.just_pressed(ids.select) {
+        return;
+    }
+    if let Some(entity) = body_at_cursor(&input, &picker, |e| bodies.contains(e)) {
+        history::perform(&mut commands, ObjectsEdit::despawned(vec![entity]));
+    }
+}
+
+/// Freezes the clicked object in place, or lets it move again if it was already frozen. This is
+/// the freeze tool's update hook.
+pub fn freeze_tool(
+    mut commands: Commands,
+    input: Res<InputState>,
+    ids: Res<BuiltinToolInputIds>,
+    picker: BodyPicker,
+    bodies: Query<(&RigidBody, Option<&Frozen>)>,
+) {
+    if !input.just_pressed(ids.select) {
+        return;
+    }
+    let Some(entity) = body_at_cursor(&input, &picker, |e| {
+        bodies
+            .get(e)
+            .is_ok_and(|(body, frozen)| *body == RigidBody::Dynamic || frozen.is_some())
+    }) else {
+        return;
+    };
+
+    match bodies.get(entity) {
+        Ok((_, Some(frozen))) => {
+            thaw(&mut commands, entity, frozen);
+            history::record(&mut commands, FreezeEdit::thawed(entity, frozen));
+        }
+        Ok((&body, None)) => {
+            freeze(&mut commands, entity, body);
+            history::record(&mut commands, FreezeEdit::frozen(entity, body));
+        }
+        Err(_) => {}
+    }
+}
+
+/// Scales the clicked object by dragging away from or towards its centre. This is the resize
+/// tool's update hook.
+pub fn resize_tool(
+    mut commands: Commands,
+    mut state: ResMut<ResizeState>,
+    input: Res<InputState>,
+    ids: Res<BuiltinToolInputIds>,
+    picker: BodyPicker,
+    mut bodies: Query<(&mut Transform, Option<&Spawned>), With<RigidBody>>,
+    registry: Res<Registry<Spawnable>>,
+) {
+    let Some(point) = input.mouse_world() else {
+        return;
+    };
+
+    if input.just_pressed(ids.select)
+        && let Some(entity) = body_at_cursor(&input, &picker, |e| bodies.contains(e))
+        && let Ok((transform, _)) = bodies.get(entity)
+    {
+        let distance = point.distance(transform.translation.truncate()).max(1.0);
+        state.target = Some((entity, transform.scale, distance));
+    }
+    if !input.pressed(ids.select) {
+        // The collider is only rebuilt once the object is let go of
+        if let Some((entity, scale, _)) = state.target.take()
+            && let Ok((transform, spawned)) = bodies.get(entity)
+        {
+            if let Some(spawned) = spawned {
+                rebuild_collider(&mut commands, &registry, entity, spawned);
+            }
+            let before = transform.with_scale(scale);
+            let edit = TransformEdit::new([(entity, before, *transform)]);
+            if !edit.is_empty() {
+                history::record(&mut commands, edit);
+            }
+        }
+        return;
+    }
+
+    let Some((entity, scale, distance)) = state.target else {
+        return;
+    };
+    let Ok((mut transform, _)) = bodies.get_mut(entity) else {
+        state.target = None;
+        return;
+    };
+    let factor = point.distance(transform.translation.truncate()).max(1.0) / distance;
+    let factor = factor.clamp(
+        MIN_SCALE / scale.min_element(),
+        MAX_SCALE / scale.max_element(),
+    );
+    transform.scale = scale * factor;
+}
+
+/// Stops resizing when switching away from the resize tool
+pub fn end_resize(mut state: ResMut<ResizeState>) {
+    state.target = None;
+}
+
+/// Paints the clicked object, with the tool's mode stepping through the colours. This is the paint
+/// tool's update hook.
+pub fn paint_tool(
+    mut commands: Commands,
+    mut state: ResMut<PaintState>,
+    input: Res<InputState>,
+    ids: Res<BuiltinToolInputIds>,
+    picker: BodyPicker,
+    mut sprites: Query<&mut Sprite, With<RigidBody>>,
+) {
+    if input.just_pressed(ids.tool_mode) {
+        state.color = (state.color + 1) % PAINT_COLORS.len();
+    }
+    if !input.just_pressed(ids.select) {
+        return;
+    }
+
+    if let Some(entity) = body_at_cursor(&input, &picker, |e| sprites.contains(e))
+        && let Ok(mut sprite) = sprites.get_mut(entity)
+        && sprite.color != state.color()
+    {
+        history::record(
+            &mut commands,
+            PaintEdit::new(entity, sprite.color, state.color()),
+        );
+        sprite.color = state.color();
+    }
+}
+
+/// Shows the paint colour at the cursor. This is the paint tool's draw hook.
+pub fn preview_paint(mut gizmos: Gizmos, state: Res<PaintState>, input: Res<InputState>) {
+    if let Some(point) = input.mouse_world() {
+        gizmos.circle_2d(point, 2.0, state.color());
+    }
+}
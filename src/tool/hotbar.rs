@@ -0,0 +1,80 @@
+use bevy::{picking::Pickable, prelude::*};
+
+use crate::{
+    modding::registry::{Id, Registry},
+    tool::{ActiveTool, Tool, tool_order},
+};
+
+const TEXT_COLOR: Color = Color::hsl(0.0, 0.0, 0.9);
+const BUTTON_COLOR: Color = Color::hsl(0.0, 0.0, 0.2);
+const HOVERED_BUTTON_COLOR: Color = Color::hsl(0.0, 0.0, 0.3);
+const ACTIVE_BUTTON_COLOR: Color = Color::hsl(210.0, 0.6, 0.35);
+
+/// The root node of the hotbar
+#[derive(Debug, Component)]
+pub struct Hotbar;
+
+/// Switches to a tool when pressed
+#[derive(Debug, Component)]
+pub(super) struct HotbarButton(Id);
+
+pub(super) fn spawn_hotbar(mut commands: Commands, registry: Res<Registry<Tool>>) {
+    let hotbar = commands
+        .spawn((
+            Hotbar,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(8.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                column_gap: Val::Px(4.0),
+                ..default()
+            },
+            Pickable::IGNORE,
+        ))
+        .id();
+
+    for id in tool_order(&registry) {
+        let Some(tool) = registry.get(id) else {
+            continue;
+        };
+        commands.spawn((
+            HotbarButton(id),
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(BUTTON_COLOR),
+            ChildOf(hotbar),
+            children![(Text::new(tool.name()), TextColor(TEXT_COLOR))],
+        ));
+    }
+}
+
+pub(super) fn hotbar_buttons(
+    buttons: Query<(&Interaction, &HotbarButton), Changed<Interaction>>,
+    mut active: ResMut<ActiveTool>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction == Interaction::Pressed {
+            active.set(button.0);
+        }
+    }
+}
+
+pub(super) fn update_hotbar(
+    mut buttons: Query<(&HotbarButton, &Interaction, &mut BackgroundColor)>,
+    active: Res<ActiveTool>,
+) {
+    for (button, interaction, mut background) in buttons.iter_mut() {
+        let color = if active.is(button.0) {
+            ACTIVE_BUTTON_COLOR
+        } else if *interaction != Interaction::None {
+            HOVERED_BUTTON_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+        background.set_if_neq(BackgroundColor(color));
+    }
+}
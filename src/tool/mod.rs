@@ -0,0 +1,292 @@
+use bevy::{ecs::system::SystemId, prelude::*};
+
+use crate::{
+    input::{InputMapping, InputState},
+    modding::{
+        PostModLoad,
+        registry::{Id, Registry},
+    },
+    tool::{
+        builtin::BuiltinToolsPlugin,
+        hotbar::{hotbar_buttons, spawn_hotbar, update_hotbar},
+    },
+};
+
+pub mod builtin;
+mod hotbar;
+
+/// The tool selected when the game starts
+pub const DEFAULT_TOOL: &str = "base::tool::select";
+
+/// Adds the tools the player uses on the world, and the hotbar to switch between them.
+pub struct ToolPlugin;
+
+impl Plugin for ToolPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(BuiltinToolsPlugin)
+            .insert_resource(Registry::<Tool>::new())
+            .init_resource::<ActiveTool>()
+            .add_systems(PostModLoad, (init_tool_input_ids, init_active_tool))
+            .add_systems(Startup, spawn_hotbar)
+            .add_systems(
+                Update,
+                (
+                    (hotbar_buttons, switch_tools),
+                    run_tools.in_set(ToolSystems),
+                    update_hotbar,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// The system running the active tool's hooks
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub struct ToolSystems;
+
+/// Something the player does to the world with the cursor, like spawning or deleting objects.
+/// Its hooks are systems registered with [`Commands::register_system`]. Scripted mods add tools
+/// through [`register_scripted_tool`] instead.
+#[derive(Debug, Clone)]
+pub struct Tool {
+    name: String,
+    /// Runs when the player switches to the tool
+    activate: Option<SystemId>,
+    /// Runs when the player switches away from the tool
+    deactivate: Option<SystemId>,
+    /// Runs every frame while the tool is active
+    update: Option<SystemId>,
+    /// Runs every frame after the update, to draw the tool's gizmos
+    draw: Option<SystemId>,
+}
+
+impl Tool {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            activate: None,
+            deactivate: None,
+            update: None,
+            draw: None,
+        }
+    }
+
+    pub fn on_activate(mut self, system: SystemId) -> Self {
+        self.activate = Some(system);
+        self
+    }
+
+    pub fn on_deactivate(mut self, system: SystemId) -> Self {
+        self.deactivate = Some(system);
+        self
+    }
+
+    pub fn on_update(mut self, system: SystemId) -> Self {
+        self.update = Some(system);
+        self
+    }
+
+    pub fn on_draw(mut self, system: SystemId) -> Self {
+        self.draw = Some(system);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A callback into a mod's script, run with the world
+pub type ScriptHook = Box<dyn FnMut(&mut World) + Send + Sync>;
+
+/// A tool added by a scripted mod, with its hooks as callbacks into the mod's script
+#[derive(Default)]
+pub struct ScriptedTool {
+    pub name: String,
+    pub activate: Option<ScriptHook>,
+    pub deactivate: Option<ScriptHook>,
+    pub update: Option<ScriptHook>,
+    pub draw: Option<ScriptHook>,
+}
+
+/// Registers a tool at `path` whose hooks call into a script, for the mod loader to use when a
+/// script adds a tool. Returns `None` if the path isn't valid.
+pub fn register_scripted_tool(world: &mut World, path: &str, tool: ScriptedTool) -> Option<Id> {
+    let mut hook = |hook: Option<ScriptHook>| {
+        hook.map(|mut hook| world.register_system(move |world: &mut World| hook(world)))
+    };
+    let tool = Tool {
+        name: tool.name,
+        activate: hook(tool.activate),
+        deactivate: hook(tool.deactivate),
+        update: hook(tool.update),
+        draw: hook(tool.draw),
+    };
+
+    let id = world
+        .resource_mut::<Registry<Tool>>()
+        .register(path, tool.clone());
+    if id.is_none() {
+        let hooks = [tool.activate, tool.deactivate, tool.update, tool.draw];
+        for system in hooks.into_iter().flatten() {
+            let _ = world.unregister_system(system);
+        }
+    }
+    id
+}
+
+/// The tool the player is using
+#[derive(Debug, Default, Resource)]
+pub struct ActiveTool {
+    current: Option<Id>,
+    /// The tool whose hooks ran last, so switching can be noticed
+    applied: Option<Id>,
+}
+
+impl ActiveTool {
+    pub fn get(&self) -> Option<Id> {
+        self.current
+    }
+
+    pub fn is(&self, id: Id) -> bool {
+        self.current == Some(id)
+    }
+
+    /// Switches to `id`. The old tool is deactivated and the new one activated when the tools
+    /// next run.
+    pub fn set(&mut self, id: Id) {
+        self.current = Some(id);
+    }
+}
+
+/// Returns the tools in the order they were registered, which is the order of the hotbar
+pub fn tool_order(registry: &Registry<Tool>) -> Vec<Id> {
+    let mut ids: Vec<Id> = registry.iter().map(|(&id, _)| id).collect();
+    ids.sort();
+    ids
+}
+
+#[derive(Debug, Resource)]
+struct ToolInputIds {
+    next: Id,
+    previous: Id,
+}
+
+fn init_tool_input_ids(mut commands: Commands, inputs: Res<Registry<InputMapping>>) {
+    let ids = ToolInputIds {
+        next: inputs.lookup("base::input::next_tool").unwrap(),
+        previous: inputs.lookup("base::input::previous_tool").unwrap(),
+    };
+    commands.insert_resource(ids);
+}
+
+fn init_active_tool(mut active: ResMut<ActiveTool>, registry: Res<Registry<Tool>>) {
+    let tool = registry
+        .lookup(DEFAULT_TOOL)
+        .or_else(|| tool_order(&registry).first().copied());
+    match tool {
+        Some(id) => active.set(id),
+        None => warn!("no tools are registered"),
+    }
+}
+
+/// Steps through the tools in hotbar order, wrapping around at the ends
+fn switch_tools(
+    mut active: ResMut<ActiveTool>,
+    input: Res<InputState>,
+    ids: Res<ToolInputIds>,
+    registry: Res<Registry<Tool>>,
+) {
+    let step: isize = match (
+        input.just_pressed(ids.next),
+        input.just_pressed(ids.previous),
+    ) {
+        (true, false) => 1,
+        (false, true) => -1,
+        _ => return,
+    };
+
+    let order = tool_order(&registry);
+    if order.is_empty() {
+        return;
+    }
+    let index = active
+        .get()
+        .and_then(|id| order.iter().position(|&t| t == id))
+        .map_or(0, |i| {
+            (i as isize + step).rem_euclid(order.len() as isize) as usize
+        });
+    active.set(order[index]);
+}
+
+/// Runs the active tool's hooks, deactivating the last tool first if the player switched
+fn run_tools(world: &mut World) {
+    let (previous, current) = {
+        let mut active = world.resource_mut::<ActiveTool>();
+        let previous = active.applied;
+        active.applied = active.current;
+        (previous, active.current)
+    };
+
+    let registry = world.resource::<Registry<Tool>>();
+    let tool = |id: Option<Id>| id.and_then(|id| registry.get(id));
+
+    let mut hooks = Vec::new();
+    if previous != current {
+        hooks.extend(tool(previous).and_then(|t| t.deactivate));
+        hooks.extend(tool(current).and_then(|t| t.activate));
+    }
+    if let Some(tool) = tool(current) {
+        hooks.extend(tool.update);
+        hooks.extend(tool.draw);
+    }
+
+    for hook in hooks {
+        if let Err(e) = world.run_system(hook) {
+            error!("error running tool hook: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default, Resource)]
+    struct Calls(Vec<&'static str>);
+
+    fn record(call: &'static str) -> Option<ScriptHook> {
+        Some(Box::new(move |world: &mut World| {
+            world.resource_mut::<Calls>().0.push(call)
+        }))
+    }
+
+    /// Registers a tool the way the mod loader would for a script, then checks its hooks run
+    #[test]
+    fn runs_scripted_tool_hooks() {
+        let mut world = World::new();
+        world.insert_resource(Registry::<Tool>::new());
+        world.init_resource::<ActiveTool>();
+        world.init_resource::<Calls>();
+
+        let tool = ScriptedTool {
+            name: "Stamp".to_string(),
+            activate: record("activate"),
+            update: record("update"),
+            ..default()
+        };
+        let id = register_scripted_tool(&mut world, "test::tool::stamp", tool).unwrap();
+        assert_eq!(
+            world.resource::<Registry<Tool>>().get(id).unwrap().name(),
+            "Stamp"
+        );
+
+        world.resource_mut::<ActiveTool>().set(id);
+        run_tools(&mut world);
+        run_tools(&mut world);
+        assert_eq!(
+            world.resource::<Calls>().0,
+            ["activate", "update", "update"]
+        );
+    }
+}